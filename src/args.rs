@@ -0,0 +1,70 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+// Arguments of a day's tool: `--name value` options, `--name` flags and
+// positional arguments, rejecting anything the tool does not know.
+pub struct Args {
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+    pub positional: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(
+        args: &[String],
+        value_names: &[&str],
+        flag_names: &[&str],
+    ) -> Result<Self, String> {
+        let mut parsed = Self {
+            values: HashMap::new(),
+            flags: HashSet::new(),
+            positional: vec![],
+        };
+        let mut rest = args.iter();
+        while let Some(arg) = rest.next() {
+            if value_names.contains(&arg.as_str()) {
+                let value = rest.next().ok_or(format!("{} needs a value", arg))?;
+                parsed.values.insert(arg.clone(), value.clone());
+            } else if flag_names.contains(&arg.as_str()) {
+                parsed.flags.insert(arg.clone());
+            } else if arg.starts_with("--") {
+                return Err(format!("Unknown argument: {}", arg));
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+
+    pub fn parsed<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.value(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("Invalid value for {}: {}", name, value)),
+            None => Ok(default),
+        }
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+}
+
+#[test]
+fn test_parse() {
+    let args: Vec<String> = vec!["--k", "3", "targets.txt", "--count"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    let parsed = Args::parse(&args, &["--k", "--target"], &["--count"]).unwrap();
+    assert_eq!(Some("3"), parsed.value("--k"));
+    assert_eq!(Ok(3), parsed.parsed("--k", 2));
+    assert_eq!(Ok(2020), parsed.parsed("--target", 2020));
+    assert!(parsed.flag("--count"));
+    assert_eq!(vec!["targets.txt".to_string()], parsed.positional);
+    assert!(Args::parse(&args, &["--k"], &[]).is_err());
+}
@@ -0,0 +1,87 @@
+use crate::runner::Solution;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub struct Cache {
+    dir: PathBuf,
+    code_version: u64,
+    refresh: bool,
+}
+
+impl Cache {
+    // The code version is a hash of the running executable, so any rebuild with
+    // changed code misses the answers stored by the previous build.
+    pub fn open(dir: PathBuf, refresh: bool, clear: bool) -> io::Result<Self> {
+        if clear && dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        let code_version = hash(&fs::read(env::current_exe()?)?);
+        Ok(Self {
+            dir: dir,
+            code_version: code_version,
+            refresh: refresh,
+        })
+    }
+
+    fn entry(&self, day: u32, part: u32, input: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{:02}-{}-{:016x}-{:016x}",
+            day,
+            part,
+            hash(input.as_bytes()),
+            self.code_version
+        ))
+    }
+
+    pub fn get(&self, day: u32, part: u32, input: &str) -> Option<Solution> {
+        if self.refresh {
+            return None;
+        }
+        let content = fs::read_to_string(self.entry(day, part, input)).ok()?;
+        let (took, answer) = content.split_at(content.find('\n')?);
+        Some(Solution {
+            answer: answer[1..].to_string(),
+            took: took.to_string(),
+        })
+    }
+
+    pub fn put(&self, day: u32, part: u32, input: &str, solution: &Solution) -> io::Result<()> {
+        fs::write(
+            self.entry(day, part, input),
+            format!("{}\n{}", solution.took, solution.answer),
+        )
+    }
+}
+
+// FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[test]
+fn test_put_and_get() {
+    let dir = env::temp_dir().join(format!("aoc-cache-test-{}", std::process::id()));
+    let cache = Cache::open(dir.clone(), false, true).unwrap();
+    let solution = Solution {
+        answer: "514579".to_string(),
+        took: "12µs".to_string(),
+    };
+    cache.put(1, 1, "1721\n979", &solution).unwrap();
+
+    let cached = cache.get(1, 1, "1721\n979").unwrap();
+    assert_eq!("514579", cached.answer);
+    assert_eq!("12µs", cached.took);
+    assert!(cache.get(1, 2, "1721\n979").is_none());
+    assert!(cache.get(1, 1, "1721\n978").is_none());
+
+    let refreshing_cache = Cache::open(dir.clone(), true, false).unwrap();
+    assert!(refreshing_cache.get(1, 1, "1721\n979").is_none());
+    let cleared_cache = Cache::open(dir.clone(), false, true).unwrap();
+    assert!(cleared_cache.get(1, 1, "1721\n979").is_none());
+    fs::remove_dir_all(dir).unwrap();
+}
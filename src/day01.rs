@@ -1,70 +1,727 @@
+use crate::args::Args;
+use crate::days::Tool;
+use num::BigInt;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+
 pub fn part1(inp: String) {
-    let numbers = transform_input(inp);
-    println!("{}", sorted_search_sum(numbers, 2020));
+    let numbers = parse_numbers(inp);
+    println!(
+        "{}",
+        product_or_no_solution(hash_search_sum(&numbers, 2020).map(|(a, b)| vec![a, b]))
+    );
 }
 
 pub fn part2(inp: String) {
-    let numbers = transform_input(inp);
-    println!("{}", search_sum_of_3(numbers, 2020));
+    println!("{}", k_sum_product(parse_numbers(inp), 3, 2020));
 }
 
-fn transform_input(inp: String) -> Vec<i32> {
-    return inp
-        .lines()
-        .map(|line: &str| line.parse().unwrap_or(0))
-        .collect();
+pub static PART1_VARIANTS: &[(&str, fn(String) -> String)] = &[
+    ("hash-set", |inp| {
+        let numbers = parse_numbers(inp);
+        product_or_no_solution(hash_search_sum(&numbers, 2020).map(|(a, b)| vec![a, b]))
+    }),
+    ("k-sum", |inp| k_sum_product(parse_numbers(inp), 2, 2020)),
+    ("brute", |inp| {
+        product_or_no_solution(search_sum(parse_numbers(inp), 2020).map(|(a, b)| vec![a, b]))
+    }),
+];
+
+pub static PART2_VARIANTS: &[(&str, fn(String) -> String)] = &[
+    ("k-sum", |inp| k_sum_product(parse_numbers(inp), 3, 2020)),
+    ("brute", |inp| {
+        product_or_no_solution(
+            search_sum_of_3(parse_numbers(inp), 2020).map(|(a, b, c)| vec![a, b, c]),
+        )
+    }),
+];
+
+pub static TOOLS: &[(&str, Tool)] = &[
+    ("queries", Tool::WithInput(run_queries)),
+    ("k-sum", Tool::WithInput(run_k_sum)),
+    ("combinations", Tool::WithInput(run_combinations)),
+];
+
+fn transform_input(inp: String) -> Result<Vec<i64>, String> {
+    inp.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .map_err(|_| format!("Line {}: {:?} is not a whole number", i + 1, line))
+        })
+        .collect()
 }
 
-#[allow(dead_code)]
-fn search_sum(numbers: Vec<i32>, sum: i32) -> i32 {
-    search_sum_with_index(numbers, sum, 0)
+fn parse_numbers(inp: String) -> Vec<i64> {
+    transform_input(inp).unwrap_or_else(|err| panic!("{}", err))
 }
 
-fn sorted_search_sum(mut numbers: Vec<i32>, sum: i32) -> i32 {
-    numbers.sort();
-    sorted_search_sum_with_start_index(numbers, sum, 0)
+fn search_sum(numbers: Vec<i64>, sum: i64) -> Option<(i64, i64)> {
+    search_sum_with_index(numbers, sum, 0)
 }
 
-fn sorted_search_sum_with_start_index(numbers: Vec<i32>, sum: i32, start_index: usize) -> i32 {
-    let mut i1 = (start_index + numbers.len() - 1) / 2;
-    let mut i2 = (start_index + numbers.len() - 1) / 2 + 1;
-    while i1 >= start_index && i2 < numbers.len() && i1 < i2 {
-        if numbers[i1] + numbers[i2] == sum {
-            return numbers[i1] * numbers[i2];
-        } else if numbers[i1] + numbers[i2] > sum {
-            if numbers[i1 - 1] + numbers[i2] < sum {
-                i2 = i2 - 1;
-            } else {
-                i1 = i1 - 1;
-            }
-        } else {
-            if numbers[i1] + numbers[i2 + 1] > sum {
-                i1 = i1 + 1;
-            } else {
-                i2 = i2 + 1;
+// Single pass: every number is checked against the complements of the numbers
+// before it, so duplicates and negative numbers need no special casing.
+fn hash_search_sum(numbers: &[i64], sum: i64) -> Option<(i64, i64)> {
+    let mut seen: HashSet<i64> = HashSet::with_capacity(numbers.len());
+    for number in numbers {
+        if let Some(complement) = sum.checked_sub(*number) {
+            if seen.contains(&complement) {
+                return Some((complement, *number));
             }
         }
+        seen.insert(*number);
     }
-    return 0;
+    None
 }
 
-fn search_sum_with_index(numbers: Vec<i32>, sum: i32, start_index: usize) -> i32 {
+fn search_sum_with_index(numbers: Vec<i64>, sum: i64, start_index: usize) -> Option<(i64, i64)> {
     for i in start_index..numbers.len() {
         for j in (i + 1)..numbers.len() {
-            if numbers[i] + numbers[j] == sum {
-                return numbers[i] * numbers[j];
+            if numbers[i].checked_add(numbers[j]) == Some(sum) {
+                return Some((numbers[i], numbers[j]));
             }
         }
     }
-    return 0;
+    return None;
 }
 
-fn search_sum_of_3(numbers: Vec<i32>, sum: i32) -> i32 {
+fn search_sum_of_3(numbers: Vec<i64>, sum: i64) -> Option<(i64, i64, i64)> {
     for i in 0..numbers.len() {
-        let product = search_sum_with_index(numbers.clone(), sum - numbers[i], i + 1);
-        if product != 0 {
-            return numbers[i] * product;
+        let rest = match sum.checked_sub(numbers[i]) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        if let Some((a, b)) = search_sum_with_index(numbers.clone(), rest, i + 1) {
+            return Some((numbers[i], a, b));
+        }
+    }
+    return None;
+}
+
+// Multiplies in i128 while that is exact and only then switches to big integers.
+fn product(values: &[i64]) -> String {
+    let exact = values
+        .iter()
+        .try_fold(1i128, |acc, value| acc.checked_mul(*value as i128));
+    match exact {
+        Some(product) => product.to_string(),
+        None => values
+            .iter()
+            .map(|value| BigInt::from(*value))
+            .product::<BigInt>()
+            .to_string(),
+    }
+}
+
+fn product_or_no_solution(values: Option<Vec<i64>>) -> String {
+    match values {
+        Some(values) => product(&values),
+        None => "no solution".to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct KSum {
+    pub values: Vec<i64>,
+    pub indices: Vec<usize>,
+}
+
+// Finds k numbers (at distinct indices) that add up to the target. The numbers
+// are searched in sorted order, pruning every prefix whose smallest or largest
+// possible completion misses the target; for k >= 4 the last two numbers come
+// from a sorted index of all pair sums (meet in the middle).
+pub fn find_k_sum(numbers: &[i64], k: usize, target: i64) -> Option<KSum> {
+    if k > numbers.len() {
+        return None;
+    }
+    let mut order: Vec<usize> = (0..numbers.len()).collect();
+    order.sort_by_key(|i| numbers[*i]);
+    let sorted: Vec<i64> = order.iter().map(|i| numbers[*i]).collect();
+    let search = KSumSearch::new(sorted, k >= 4);
+    let mut chosen = Vec::with_capacity(k);
+    if !search.search(k, target as i128, 0, &mut chosen) {
+        return None;
+    }
+    let mut indices: Vec<usize> = chosen.iter().map(|pos| order[*pos]).collect();
+    indices.sort();
+    Some(KSum {
+        values: indices.iter().map(|i| numbers[*i]).collect(),
+        indices: indices,
+    })
+}
+
+// Lists every distinct combination of values (ascending within a combination)
+// of k numbers that add up to the target. Repeated values are only used as
+// often as they occur in the input.
+pub fn find_all_k_sums(numbers: &[i64], k: usize, target: i64) -> Vec<Vec<i64>> {
+    if k > numbers.len() {
+        return vec![];
+    }
+    let mut sorted = numbers.to_vec();
+    sorted.sort();
+    let search = KSumSearch::new(sorted, false);
+    let mut found = vec![];
+    search.search_all(k, target as i128, 0, &mut vec![], &mut found);
+    found
+        .iter()
+        .map(|positions| positions.iter().map(|pos| search.sorted[*pos]).collect())
+        .collect()
+}
+
+// Counts the combinations of entries (not of values) that add up to the target,
+// so two equal entries make two different combinations. `ways[j]` holds how
+// many combinations of j entries seen so far reach each sum, so nothing is
+// enumerated. None when the count does not fit in a u128.
+pub fn count_k_sums(numbers: &[i64], k: usize, target: i64) -> Option<u128> {
+    let mut ways: Vec<HashMap<i128, u128>> = vec![HashMap::new(); k + 1];
+    ways[0].insert(0, 1);
+    for number in numbers {
+        for j in (1..=k.min(numbers.len())).rev() {
+            let extended: Vec<(i128, u128)> = ways[j - 1]
+                .iter()
+                .map(|(sum, count)| (sum + *number as i128, *count))
+                .collect();
+            for (sum, count) in extended {
+                let total = ways[j].entry(sum).or_insert(0);
+                *total = total.checked_add(count)?;
+            }
+        }
+    }
+    Some(ways[k].get(&(target as i128)).cloned().unwrap_or(0))
+}
+
+struct KSumSearch {
+    sorted: Vec<i64>,
+    prefix_sums: Vec<i128>,
+    pair_sums: Vec<(i128, usize, usize)>,
+}
+
+impl KSumSearch {
+    fn new(sorted: Vec<i64>, with_pair_sums: bool) -> Self {
+        let mut prefix_sums = vec![0];
+        for number in sorted.iter() {
+            prefix_sums.push(prefix_sums.last().unwrap() + *number as i128);
+        }
+        let mut pair_sums = vec![];
+        if with_pair_sums {
+            for i in 0..sorted.len() {
+                for j in (i + 1)..sorted.len() {
+                    pair_sums.push((sorted[i] as i128 + sorted[j] as i128, i, j));
+                }
+            }
+            pair_sums.sort();
+        }
+        Self {
+            sorted: sorted,
+            prefix_sums: prefix_sums,
+            pair_sums: pair_sums,
+        }
+    }
+
+    fn smallest_sum(&self, start: usize, k: usize) -> i128 {
+        self.prefix_sums[start + k] - self.prefix_sums[start]
+    }
+
+    fn largest_sum(&self, k: usize) -> i128 {
+        let n = self.sorted.len();
+        self.prefix_sums[n] - self.prefix_sums[n - k]
+    }
+
+    fn search(&self, k: usize, target: i128, start: usize, chosen: &mut Vec<usize>) -> bool {
+        let n = self.sorted.len();
+        if k == 0 {
+            return target == 0;
+        }
+        if n - start < k || target < self.smallest_sum(start, k) || target > self.largest_sum(k) {
+            return false;
+        }
+        if k == 2 && !self.pair_sums.is_empty() {
+            return self.search_pair_sums(target, start, chosen);
+        }
+        if k == 2 {
+            return self.search_two_pointer(target, start, chosen);
+        }
+        for i in start..=(n - k) {
+            if i > start && self.sorted[i] == self.sorted[i - 1] {
+                continue;
+            }
+            if self.smallest_sum(i, k) > target {
+                break;
+            }
+            if self.sorted[i] as i128 + self.largest_sum(k - 1) < target {
+                continue;
+            }
+            chosen.push(i);
+            if self.search(k - 1, target - self.sorted[i] as i128, i + 1, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+        false
+    }
+
+    fn search_all(
+        &self,
+        k: usize,
+        target: i128,
+        start: usize,
+        chosen: &mut Vec<usize>,
+        found: &mut Vec<Vec<usize>>,
+    ) {
+        let n = self.sorted.len();
+        if k == 0 {
+            if target == 0 {
+                found.push(chosen.clone());
+            }
+            return;
+        }
+        if n - start < k || target < self.smallest_sum(start, k) || target > self.largest_sum(k) {
+            return;
+        }
+        if k == 2 {
+            let mut i1 = start;
+            let mut i2 = n - 1;
+            while i1 < i2 {
+                let sum = self.sorted[i1] as i128 + self.sorted[i2] as i128;
+                if sum == target {
+                    chosen.push(i1);
+                    chosen.push(i2);
+                    found.push(chosen.clone());
+                    chosen.truncate(chosen.len() - 2);
+                }
+                if sum <= target {
+                    i1 += 1;
+                    while i1 < i2 && self.sorted[i1] == self.sorted[i1 - 1] {
+                        i1 += 1;
+                    }
+                } else {
+                    i2 -= 1;
+                }
+            }
+            return;
+        }
+        for i in start..=(n - k) {
+            if i > start && self.sorted[i] == self.sorted[i - 1] {
+                continue;
+            }
+            if self.smallest_sum(i, k) > target {
+                break;
+            }
+            chosen.push(i);
+            self.search_all(k - 1, target - self.sorted[i] as i128, i + 1, chosen, found);
+            chosen.pop();
+        }
+    }
+
+    fn search_two_pointer(&self, target: i128, start: usize, chosen: &mut Vec<usize>) -> bool {
+        let mut i1 = start;
+        let mut i2 = self.sorted.len() - 1;
+        while i1 < i2 {
+            let sum = self.sorted[i1] as i128 + self.sorted[i2] as i128;
+            if sum == target {
+                chosen.push(i1);
+                chosen.push(i2);
+                return true;
+            } else if sum > target {
+                i2 -= 1;
+            } else {
+                i1 += 1;
+            }
+        }
+        false
+    }
+
+    fn search_pair_sums(&self, target: i128, start: usize, chosen: &mut Vec<usize>) -> bool {
+        let first = self
+            .pair_sums
+            .partition_point(|(sum, i, _)| (*sum, *i) < (target, start));
+        match self.pair_sums.get(first) {
+            Some((sum, i, j)) if *sum == target => {
+                chosen.push(*i);
+                chosen.push(*j);
+                true
+            }
+            _ => false,
         }
     }
-    return 0;
+}
+
+fn k_sum_product(numbers: Vec<i64>, k: usize, target: i64) -> String {
+    product_or_no_solution(find_k_sum(&numbers, k, target).map(|k_sum| k_sum.values))
+}
+
+// Answers "which two / three entries sum to X" for many X against the same
+// list: values are indexed once, as are all pair sums (keeping, per sum, enough
+// pairs that one of them avoids any given third entry).
+pub struct SumIndex {
+    numbers: Vec<i64>,
+    positions: HashMap<i64, Vec<usize>>,
+    pair_sums: HashMap<i64, Vec<(usize, usize)>>,
+}
+
+impl SumIndex {
+    pub fn new(numbers: &[i64]) -> Self {
+        let mut positions: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (i, number) in numbers.iter().enumerate() {
+            positions.entry(*number).or_default().push(i);
+        }
+        let mut pair_sums: HashMap<i64, Vec<(usize, usize)>> = HashMap::new();
+        for i in 0..numbers.len() {
+            for j in (i + 1)..numbers.len() {
+                let sum = match numbers[i].checked_add(numbers[j]) {
+                    Some(sum) => sum,
+                    None => continue,
+                };
+                let pairs = pair_sums.entry(sum).or_default();
+                match pairs.first().cloned() {
+                    None => pairs.push((i, j)),
+                    Some((a, b)) => {
+                        for avoided in &[a, b] {
+                            let avoids = |(x, y): &(usize, usize)| x != avoided && y != avoided;
+                            if avoids(&(i, j)) && !pairs.iter().any(avoids) {
+                                pairs.push((i, j));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            numbers: numbers.to_vec(),
+            positions: positions,
+            pair_sums: pair_sums,
+        }
+    }
+
+    pub fn two_sum(&self, target: i64) -> Option<Vec<usize>> {
+        for (i, number) in self.numbers.iter().enumerate() {
+            let complement = match target.checked_sub(*number) {
+                Some(complement) => complement,
+                None => continue,
+            };
+            let j = self
+                .positions
+                .get(&complement)
+                .and_then(|positions| positions.iter().find(|j| **j != i));
+            if let Some(j) = j {
+                return Some(vec![i.min(*j), i.max(*j)]);
+            }
+        }
+        None
+    }
+
+    pub fn three_sum(&self, target: i64) -> Option<Vec<usize>> {
+        for (k, number) in self.numbers.iter().enumerate() {
+            let rest = match target.checked_sub(*number) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let pair = self
+                .pair_sums
+                .get(&rest)
+                .and_then(|pairs| pairs.iter().find(|(i, j)| *i != k && *j != k));
+            if let Some((i, j)) = pair {
+                let mut indices = vec![*i, *j, k];
+                indices.sort();
+                return Some(indices);
+            }
+        }
+        None
+    }
+
+    fn describe(&self, indices: Option<Vec<usize>>) -> String {
+        match indices {
+            Some(indices) => {
+                let values: Vec<i64> = indices.iter().map(|i| self.numbers[*i]).collect();
+                format!(
+                    "{} (product {})",
+                    values
+                        .iter()
+                        .map(|value| value.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" + "),
+                    product(&values)
+                )
+            }
+            None => "no solution".to_string(),
+        }
+    }
+
+    pub fn answer(&self, target: i64) -> String {
+        format!(
+            "{}: 2-sum {}, 3-sum {}",
+            target,
+            self.describe(self.two_sum(target)),
+            self.describe(self.three_sum(target))
+        )
+    }
+}
+
+// `queries [targets file|-]`: one line per target, read from stdin by default.
+fn run_queries(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[], &[])?;
+    let targets_str = match args.positional.first().map(|s| s.as_str()) {
+        None | Some("-") => {
+            let mut targets_str = String::new();
+            io::stdin()
+                .read_to_string(&mut targets_str)
+                .map_err(|err| err.to_string())?;
+            targets_str
+        }
+        Some(path) => fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?,
+    };
+    let targets = targets_str
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.trim()
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid target: {}", line))
+        })
+        .collect::<Result<Vec<i64>, String>>()?;
+    let numbers = transform_input(inp)?;
+    let index = SumIndex::new(&numbers);
+    for target in targets {
+        println!("{}", index.answer(target));
+    }
+    Ok(())
+}
+
+// `k-sum [--k 2] [--target 2020]`: the first k entries adding up to the target.
+fn run_k_sum(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--k", "--target"], &[])?;
+    let numbers = transform_input(inp)?;
+    match find_k_sum(
+        &numbers,
+        args.parsed("--k", 2)?,
+        args.parsed("--target", 2020)?,
+    ) {
+        Some(k_sum) => {
+            for (index, value) in k_sum.indices.iter().zip(k_sum.values.iter()) {
+                println!("line {}: {}", index + 1, value);
+            }
+        }
+        None => println!("no solution"),
+    }
+    Ok(())
+}
+
+// `combinations [--k 2] [--target 2020] [--count]`: every distinct combination
+// of values, or only the number of combinations of entries.
+fn run_combinations(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--k", "--target"], &["--count"])?;
+    let numbers = transform_input(inp)?;
+    let k = args.parsed("--k", 2)?;
+    let target = args.parsed("--target", 2020)?;
+    if args.flag("--count") {
+        match count_k_sums(&numbers, k, target) {
+            Some(count) => println!("{}", count),
+            None => return Err("Too many combinations to count".to_string()),
+        }
+        return Ok(());
+    }
+    let combinations = find_all_k_sums(&numbers, k, target);
+    if combinations.is_empty() {
+        println!("no solution");
+    }
+    for values in combinations {
+        println!(
+            "{}",
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(" + ")
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_variants_agree_on_example() {
+    let example = "1721\n979\n366\n299\n675\n1456".to_string();
+    for (name, variant) in PART1_VARIANTS {
+        assert_eq!("514579", variant(example.clone()), "variant {}", name);
+    }
+    for (name, variant) in PART2_VARIANTS {
+        assert_eq!("241861950", variant(example.clone()), "variant {}", name);
+    }
+}
+
+#[test]
+fn test_find_k_sum() {
+    let numbers = [1721, 979, 366, 299, 675, 1456];
+    assert_eq!(
+        Some(KSum {
+            values: vec![1721, 299],
+            indices: vec![0, 3],
+        }),
+        find_k_sum(&numbers, 2, 2020)
+    );
+    assert_eq!(
+        Some(KSum {
+            values: vec![979, 366, 675],
+            indices: vec![1, 2, 4],
+        }),
+        find_k_sum(&numbers, 3, 2020)
+    );
+    assert_eq!(
+        vec![0, 1, 3, 5],
+        find_k_sum(&numbers, 4, 4455).unwrap().indices
+    );
+    assert_eq!(None, find_k_sum(&numbers, 4, 4456));
+    assert_eq!(None, find_k_sum(&numbers, 7, 5496));
+    assert_eq!(vec![2, 3], find_k_sum(&[5, 5, 3, 3], 2, 6).unwrap().indices);
+}
+
+#[test]
+fn test_find_k_sum_on_many_numbers() {
+    let numbers: Vec<i64> = (0..2000).map(|i| (i * 7919) % 100_003).collect();
+    for k in 4..=6 {
+        let target: i64 = [7, 500, 900, 1300, 1700, 1999][..k]
+            .iter()
+            .map(|i| numbers[*i])
+            .sum();
+        let k_sum = find_k_sum(&numbers, k, target).unwrap();
+        assert_eq!(k, k_sum.indices.len());
+        assert_eq!(target, k_sum.values.iter().sum::<i64>());
+    }
+}
+
+#[test]
+fn test_find_all_k_sums() {
+    let numbers = [1010, 1010, 1010, 1000, 1020, 0, 2020, 5];
+    assert_eq!(
+        vec![vec![0, 2020], vec![1000, 1020], vec![1010, 1010]],
+        find_all_k_sums(&numbers, 2, 2020)
+    );
+    assert_eq!(
+        vec![vec![0, 1000, 1020], vec![0, 1010, 1010]],
+        find_all_k_sums(&numbers, 3, 2020)
+    );
+    assert_eq!(
+        vec![vec![1010, 1010]],
+        find_all_k_sums(&[1010, 1010], 2, 2020)
+    );
+    assert!(find_all_k_sums(&[1010, 5], 2, 2020).is_empty());
+}
+
+#[test]
+fn test_count_k_sums() {
+    let numbers = [1010, 1010, 1010, 1000, 1020, 0, 2020, 5];
+    assert_eq!(Some(5), count_k_sums(&numbers, 2, 2020));
+    assert_eq!(Some(4), count_k_sums(&numbers, 3, 2020));
+    assert_eq!(Some(0), count_k_sums(&[1010], 2, 2020));
+    assert_eq!(Some(1), count_k_sums(&[], 0, 0));
+
+    // All C(100, 50) combinations of 100 zeros sum to zero, more than a u64
+    // holds; C(200, 100) is more than a u128 holds.
+    assert_eq!(
+        Some(100891344545564193334812497256),
+        count_k_sums(&[0; 100], 50, 0)
+    );
+    assert_eq!(None, count_k_sums(&[0; 200], 100, 0));
+}
+
+#[test]
+fn test_k_sum_product_reports_no_solution() {
+    assert_eq!("0", k_sum_product(vec![0, 2020], 2, 2020));
+    assert_eq!("no solution", k_sum_product(vec![1, 2], 2, 2020));
+}
+
+#[test]
+fn test_hash_search_sum_edge_cases() {
+    assert_eq!(Some((1010, 1010)), hash_search_sum(&[1010, 3, 1010], 2020));
+    assert_eq!(None, hash_search_sum(&[1010, 3], 2020));
+    assert_eq!(Some((2030, -10)), hash_search_sum(&[2030, 5, -10], 2020));
+    assert_eq!(Some((0, 2020)), hash_search_sum(&[0, 1, 2, 2020], 2020));
+    assert_eq!(None, hash_search_sum(&[], 2020));
+    assert_eq!(None, hash_search_sum(&[i64::MIN, i64::MAX], i64::MAX));
+}
+
+#[test]
+fn test_hash_search_sum_against_search_sum() {
+    let mut rng = crate::rng::Rng::new(2020);
+    for _ in 0..2000 {
+        let len = rng.range(0, 20) as usize;
+        let numbers: Vec<i64> = (0..len).map(|_| rng.range(-50, 50)).collect();
+        let sum = rng.range(-100, 100);
+        let expected = search_sum(numbers.clone(), sum);
+        let found = hash_search_sum(&numbers, sum);
+        assert_eq!(expected.is_some(), found.is_some(), "{:?} {}", numbers, sum);
+        if let Some((a, b)) = found {
+            assert_eq!(sum, a + b);
+            let mut rest = numbers.clone();
+            rest.remove(rest.iter().position(|n| *n == a).unwrap());
+            assert!(rest.contains(&b), "{:?} {}", numbers, sum);
+        }
+    }
+}
+
+#[test]
+fn test_sum_index() {
+    let index = SumIndex::new(&[1721, 979, 366, 299, 675, 1456]);
+    assert_eq!(Some(vec![0, 3]), index.two_sum(2020));
+    assert_eq!(Some(vec![1, 2, 4]), index.three_sum(2020));
+    assert_eq!(None, index.two_sum(1721 * 2));
+    assert_eq!(
+        "2020: 2-sum 1721 + 299 (product 514579), 3-sum 979 + 366 + 675 (product 241861950)",
+        index.answer(2020)
+    );
+    assert_eq!("1: 2-sum no solution, 3-sum no solution", index.answer(1));
+}
+
+#[test]
+fn test_sum_index_with_duplicates() {
+    let index = SumIndex::new(&[5, 5, 5, 1]);
+    assert_eq!(Some(vec![0, 1]), index.two_sum(10));
+    assert_eq!(Some(vec![0, 1, 2]), index.three_sum(15));
+    assert_eq!(None, index.two_sum(2));
+    assert_eq!(None, index.three_sum(7));
+    let mut rng = crate::rng::Rng::new(34);
+    for _ in 0..300 {
+        let numbers: Vec<i64> = (0..rng.range(0, 12)).map(|_| rng.range(-5, 6)).collect();
+        let index = SumIndex::new(&numbers);
+        for target in -16..=16 {
+            assert_eq!(
+                find_k_sum(&numbers, 3, target).is_some(),
+                index.three_sum(target).is_some(),
+                "{:?} {}",
+                numbers,
+                target
+            );
+        }
+    }
+}
+
+#[test]
+fn test_transform_input_rejects_malformed_lines() {
+    assert_eq!(
+        Ok(vec![1721, -979, 366]),
+        transform_input("1721\n-979\n 366 \n\n".to_string())
+    );
+    assert_eq!(
+        Err("Line 2: \"97x\" is not a whole number".to_string()),
+        transform_input("1721\n97x\n366".to_string())
+    );
+    assert!(transform_input("99999999999999999999".to_string()).is_err());
+}
+
+#[test]
+fn test_product_does_not_overflow() {
+    assert_eq!("514579", product(&[1721, 299]));
+    assert_eq!("-6", product(&[-2, 3]));
+    assert_eq!(
+        "85070591730234615847396907784232501249",
+        product(&[i64::MAX, i64::MAX])
+    );
+    assert_eq!(
+        "-784637716923335095224261902710254454442933591094742482943",
+        product(&[i64::MAX, i64::MAX, i64::MIN + 1])
+    );
+    assert_eq!("1", product(&[]));
 }
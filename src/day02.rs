@@ -1,32 +1,622 @@
+use crate::args::Args;
+use crate::days::Tool;
+use crate::json;
+use crate::rng::Rng;
 use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 use std::ops::RangeInclusive;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn part1(inp: String) {
-    compute_valid_passwords_count(inp, is_valid_password1);
+    compute_valid_passwords_count(inp, "count-in-range");
 }
 
 pub fn part2(inp: String) {
-    compute_valid_passwords_count(inp, is_valid_password2);
+    compute_valid_passwords_count(inp, "exactly-one-position");
 }
 
-type Policy = (RangeInclusive<i32>, char);
-type PasswordValidator = fn(Policy, &str) -> bool;
+pub static TOOLS: &[(&str, Tool)] = &[
+    ("check", Tool::WithInput(run_check)),
+    ("audit", Tool::WithInput(run_audit)),
+    ("generate", Tool::Standalone(run_generate)),
+    ("stream", Tool::Standalone(run_stream)),
+];
 
-fn compute_valid_passwords_count(inp: String, password_validator: PasswordValidator) {
-    let valid_passwords_count = inp
-        .lines()
-        .filter(|line: &&str| {
-            let splits: Vec<&str> = line.split(":").collect();
-            match splits[..] {
-                [policy, password] => password_validator(parse_policy(policy), password.trim()),
-                _ => panic!("Found wrong entry {:?}", splits),
+// A password rule, built by one of the `POLICY_KINDS` from the text before the
+// colon of an entry.
+trait Policy {
+    fn is_valid(&self, password: &str) -> bool;
+    // Why the password is valid or not, e.g. "character 'a' occurs 5 times, allowed 1-3".
+    fn explain(&self, password: &str) -> String;
+}
+
+type PolicyParser = fn(&str) -> Result<Box<dyn Policy>, String>;
+
+static POLICY_KINDS: &[(&str, PolicyParser)] = &[
+    ("count-in-range", |rule| {
+        Ok(Box::new(CountInRange(parse_policy(rule)?)))
+    }),
+    ("exactly-one-position", |rule| {
+        Ok(Box::new(ExactlyOnePosition(parse_policy(rule)?)))
+    }),
+    ("none-of-positions", |rule| {
+        Ok(Box::new(NoneOfPositions(parse_policy(rule)?)))
+    }),
+    ("must-contain-all-of", |rule| {
+        Ok(Box::new(MustContainAllOf(parse_text(rule)?)))
+    }),
+    ("forbidden-substring", |rule| {
+        Ok(Box::new(ForbiddenSubstring(parse_text(rule)?)))
+    }),
+];
+
+type CharPolicy = (RangeInclusive<i32>, char);
+
+struct CountInRange(CharPolicy);
+
+impl Policy for CountInRange {
+    fn is_valid(&self, password: &str) -> bool {
+        is_valid_password1(self.0.clone(), password)
+    }
+
+    fn explain(&self, password: &str) -> String {
+        let (range, character) = &self.0;
+        let count = password.chars().filter(|c| c == character).count();
+        format!(
+            "character '{}' occurs {} time{}, allowed {}-{}",
+            character,
+            count,
+            if count == 1 { "" } else { "s" },
+            range.start(),
+            range.end()
+        )
+    }
+}
+
+struct ExactlyOnePosition(CharPolicy);
+
+impl Policy for ExactlyOnePosition {
+    fn is_valid(&self, password: &str) -> bool {
+        is_valid_password2(self.0.clone(), password)
+    }
+
+    fn explain(&self, password: &str) -> String {
+        explain_positions(&self.0, password)
+    }
+}
+
+struct NoneOfPositions(CharPolicy);
+
+impl Policy for NoneOfPositions {
+    fn is_valid(&self, password: &str) -> bool {
+        let (positions, character) = &self.0;
+        char_at(password, *positions.start()) != Some(*character)
+            && char_at(password, *positions.end()) != Some(*character)
+    }
+
+    fn explain(&self, password: &str) -> String {
+        explain_positions(&self.0, password)
+    }
+}
+
+struct MustContainAllOf(String);
+
+impl Policy for MustContainAllOf {
+    fn is_valid(&self, password: &str) -> bool {
+        self.0.chars().all(|c| password.contains(c))
+    }
+
+    fn explain(&self, password: &str) -> String {
+        let missing: String = self.0.chars().filter(|c| !password.contains(*c)).collect();
+        match missing.as_str() {
+            "" => format!("contains all of '{}'", self.0),
+            _ => format!("missing '{}' of '{}'", missing, self.0),
+        }
+    }
+}
+
+struct ForbiddenSubstring(String);
+
+impl Policy for ForbiddenSubstring {
+    fn is_valid(&self, password: &str) -> bool {
+        !password.contains(&self.0)
+    }
+
+    fn explain(&self, password: &str) -> String {
+        match password.find(&self.0) {
+            Some(index) => format!(
+                "contains forbidden '{}' at position {}",
+                self.0,
+                password[..index].chars().count() + 1
+            ),
+            None => format!("does not contain '{}'", self.0),
+        }
+    }
+}
+
+fn explain_positions(policy: &CharPolicy, password: &str) -> String {
+    let (positions, character) = policy;
+    let (start, end) = (*positions.start(), *positions.end());
+    match (
+        char_at(password, start) == Some(*character),
+        char_at(password, end) == Some(*character),
+    ) {
+        (true, true) => format!(
+            "positions {} and {} both contain '{}'",
+            start, end, character
+        ),
+        (true, false) => format!("only position {} contains '{}'", start, character),
+        (false, true) => format!("only position {} contains '{}'", end, character),
+        (false, false) => format!(
+            "neither position {} nor {} contains '{}'",
+            start, end, character
+        ),
+    }
+}
+
+fn policy_kind(name: &str) -> Result<PolicyParser, String> {
+    POLICY_KINDS
+        .iter()
+        .find(|(kind, _)| *kind == name)
+        .map(|(_, parser)| *parser)
+        .ok_or(format!(
+            "Unknown policy {}, available: {:?}",
+            name,
+            POLICY_KINDS
+                .iter()
+                .map(|(kind, _)| *kind)
+                .collect::<Vec<&str>>()
+        ))
+}
+
+// The main CLI's `--policy <kind>`, which replaces the policy of both parts.
+pub fn solve_with_policy(inp: String, policy_name: &str) -> Result<String, String> {
+    count_valid_passwords(&inp, policy_name).map(|count| count.to_string())
+}
+
+fn compute_valid_passwords_count(inp: String, policy_name: &str) {
+    match count_valid_passwords(&inp, policy_name) {
+        Ok(count) => println!("{}", count),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+fn count_valid_passwords(inp: &str, policy_name: &str) -> Result<usize, String> {
+    let parser = policy_kind(policy_name)?;
+    let mut count = 0;
+    for line in inp.lines() {
+        let (rule, password) = parse_entry(line)?;
+        if parser(rule)?.is_valid(password) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct StreamCounts {
+    lines: usize,
+    valid: usize,
+    invalid: usize,
+    malformed: usize,
+}
+
+impl StreamCounts {
+    fn add(&mut self, other: &StreamCounts) {
+        self.lines += other.lines;
+        self.valid += other.valid;
+        self.invalid += other.invalid;
+        self.malformed += other.malformed;
+    }
+}
+
+// Entries with their line numbers.
+type Chunk = Vec<(usize, Vec<u8>)>;
+
+// Counts one chunk; malformed lines come back as messages instead of failing.
+fn validate_chunk(parser: PolicyParser, chunk: &Chunk) -> (StreamCounts, Vec<String>) {
+    let mut counts = StreamCounts::default();
+    let mut errors = vec![];
+    for (line_number, bytes) in chunk {
+        counts.lines += 1;
+        let checked = std::str::from_utf8(bytes)
+            .map_err(|_| "Found non UTF-8 entry".to_string())
+            .and_then(|line| {
+                let (rule, password) = parse_entry(line)?;
+                Ok(parser(rule)?.is_valid(password))
+            });
+        match checked {
+            Ok(true) => counts.valid += 1,
+            Ok(false) => counts.invalid += 1,
+            Err(err) => {
+                counts.malformed += 1;
+                errors.push(format!("Line {}: {}", line_number, err));
+            }
+        }
+    }
+    (counts, errors)
+}
+
+// Reads entries line by line and hands chunks of them to `threads` workers.
+// At most two chunks per worker are in flight, which bounds the memory used
+// however large the dump is. Blank lines are skipped.
+fn validate_stream<R: BufRead>(
+    mut reader: R,
+    policy_name: &str,
+    threads: usize,
+    chunk_lines: usize,
+    on_progress: &mut dyn FnMut(&StreamCounts, Vec<String>),
+) -> Result<StreamCounts, String> {
+    let parser = policy_kind(policy_name)?;
+    let (chunk_sender, chunk_receiver) = mpsc::sync_channel::<Chunk>(threads.max(1) * 2);
+    let chunk_receiver = Arc::new(Mutex::new(chunk_receiver));
+    let (result_sender, result_receiver) = mpsc::channel();
+    let workers: Vec<thread::JoinHandle<()>> = (0..threads.max(1))
+        .map(|_| {
+            let chunk_receiver = chunk_receiver.clone();
+            let result_sender = result_sender.clone();
+            thread::spawn(move || loop {
+                let chunk = match chunk_receiver.lock().unwrap().recv() {
+                    Ok(chunk) => chunk,
+                    Err(_) => return,
+                };
+                if result_sender.send(validate_chunk(parser, &chunk)).is_err() {
+                    return;
+                }
+            })
+        })
+        .collect();
+    drop(result_sender);
+
+    let mut totals = StreamCounts::default();
+    let mut merge = |(counts, errors): (StreamCounts, Vec<String>)| {
+        totals.add(&counts);
+        on_progress(&totals, errors);
+    };
+    let mut line_number = 0;
+    let mut chunk: Chunk = vec![];
+    let mut read_error = None;
+    loop {
+        let mut line = vec![];
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                read_error = Some(err.to_string());
+                break;
+            }
+        }
+        line_number += 1;
+        while line.last() == Some(&b'\n') || line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if line.iter().all(|byte| byte.is_ascii_whitespace()) {
+            continue;
+        }
+        chunk.push((line_number, line));
+        if chunk.len() >= chunk_lines {
+            chunk_sender
+                .send(std::mem::replace(&mut chunk, vec![]))
+                .map_err(|err| err.to_string())?;
+            while let Ok(result) = result_receiver.try_recv() {
+                merge(result);
             }
+        }
+    }
+    if !chunk.is_empty() {
+        chunk_sender.send(chunk).map_err(|err| err.to_string())?;
+    }
+    drop(chunk_sender);
+    for result in result_receiver {
+        merge(result);
+    }
+    for worker in workers {
+        worker.join().map_err(|_| "A worker panicked".to_string())?;
+    }
+    match read_error {
+        Some(err) => Err(format!("Error after line {}: {}", line_number, err)),
+        None => Ok(totals),
+    }
+}
+
+struct Decision {
+    line: usize,
+    policy: String,
+    password: String,
+    valid: bool,
+    explanation: String,
+}
+
+fn audit(inp: &str, policy_name: &str) -> Result<Vec<Decision>, String> {
+    let parser = policy_kind(policy_name)?;
+    let mut decisions = vec![];
+    for (i, line) in inp.lines().enumerate() {
+        let (rule, password) =
+            parse_entry(line).map_err(|err| format!("Line {}: {}", i + 1, err))?;
+        let policy = parser(rule).map_err(|err| format!("Line {}: {}", i + 1, err))?;
+        decisions.push(Decision {
+            line: i + 1,
+            policy: rule.trim().to_string(),
+            password: password.to_string(),
+            valid: policy.is_valid(password),
+            explanation: policy.explain(password),
+        });
+    }
+    Ok(decisions)
+}
+
+fn verdict(valid: bool) -> &'static str {
+    if valid {
+        "valid"
+    } else {
+        "invalid"
+    }
+}
+
+fn render_audit_text(policy_name: &str, decisions: &[Decision]) -> String {
+    let valid_count = decisions.iter().filter(|decision| decision.valid).count();
+    let mut lines: Vec<String> = decisions
+        .iter()
+        .map(|decision| {
+            format!(
+                "{}: {} {} {}: {}",
+                decision.line,
+                decision.policy,
+                decision.password,
+                verdict(decision.valid),
+                decision.explanation
+            )
         })
-        .count();
-    println!("{}", valid_passwords_count);
+        .collect();
+    lines.push(format!(
+        "{} entries checked with {}: {} valid, {} invalid",
+        decisions.len(),
+        policy_name,
+        valid_count,
+        decisions.len() - valid_count
+    ));
+    lines.join("\n")
 }
 
-fn is_valid_password1(policy: Policy, password: &str) -> bool {
+fn render_audit_json(policy_name: &str, decisions: &[Decision]) -> String {
+    let valid_count = decisions.iter().filter(|decision| decision.valid).count();
+    let entries: Vec<String> = decisions
+        .iter()
+        .map(|decision| {
+            json::object(&[
+                ("line", decision.line.to_string()),
+                ("policy", json::string(&decision.policy)),
+                ("password", json::string(&decision.password)),
+                ("verdict", json::string(verdict(decision.valid))),
+                ("explanation", json::string(&decision.explanation)),
+            ])
+        })
+        .collect();
+    json::object(&[
+        ("policy_kind", json::string(policy_name)),
+        ("entries", json::array(&entries)),
+        (
+            "summary",
+            json::object(&[
+                ("total", decisions.len().to_string()),
+                ("valid", valid_count.to_string()),
+                ("invalid", (decisions.len() - valid_count).to_string()),
+            ]),
+        ),
+    ])
+}
+
+// Which of the two puzzle policies a generated password has to satisfy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Policy1,
+    Policy2,
+    Both,
+    ExactlyOne,
+}
+
+static TARGETS: &[(&str, Target)] = &[
+    ("policy1", Target::Policy1),
+    ("policy2", Target::Policy2),
+    ("both", Target::Both),
+    ("exactly-one", Target::ExactlyOne),
+];
+
+impl Target {
+    fn accepts(self, valid1: bool, valid2: bool) -> bool {
+        match self {
+            Target::Policy1 => valid1,
+            Target::Policy2 => valid2,
+            Target::Both => valid1 && valid2,
+            Target::ExactlyOne => valid1 != valid2,
+        }
+    }
+}
+
+// How often the policy character occurs in a password and whether it sits at
+// the first and second position of the policy. The shape alone decides both
+// policies, so generating means picking a shape and filling in the rest.
+#[derive(Clone, Copy, Debug)]
+struct Shape {
+    at_start: bool,
+    at_end: bool,
+    count: usize,
+}
+
+struct PasswordGenerator {
+    character: char,
+    length: usize,
+    start: Option<usize>,
+    end: Option<usize>,
+    others: Vec<char>,
+    shapes: Vec<Shape>,
+}
+
+impl PasswordGenerator {
+    fn new(
+        policy: CharPolicy,
+        length: usize,
+        target: Target,
+        alphabet: &str,
+    ) -> Result<Self, String> {
+        let (range, character) = policy;
+        let mut others: Vec<char> = alphabet.chars().filter(|c| *c != character).collect();
+        others.sort();
+        others.dedup();
+        if others.is_empty() {
+            return Err(format!(
+                "The alphabet needs a character other than '{}'",
+                character
+            ));
+        }
+        let index = |position: i32| match position {
+            p if p >= 1 && p as usize <= length => Some(p as usize - 1),
+            _ => None,
+        };
+        let (start, end) = (index(*range.start()), index(*range.end()));
+        let reserved = if start == end {
+            start.iter().count()
+        } else {
+            start.iter().count() + end.iter().count()
+        };
+        let mut shapes = vec![];
+        for &at_start in &[false, true] {
+            for &at_end in &[false, true] {
+                if (at_start && start.is_none())
+                    || (at_end && end.is_none())
+                    || (start == end && at_start != at_end)
+                {
+                    continue;
+                }
+                let fixed = if start == end {
+                    at_start as usize
+                } else {
+                    at_start as usize + at_end as usize
+                };
+                for count in fixed..=fixed + length - reserved {
+                    if target.accepts(range.contains(&(count as i32)), at_start != at_end) {
+                        shapes.push(Shape {
+                            at_start: at_start,
+                            at_end: at_end,
+                            count: count,
+                        });
+                    }
+                }
+            }
+        }
+        if shapes.is_empty() {
+            return Err(format!(
+                "No password of length {} satisfies {:?} for {}-{} {}",
+                length,
+                target,
+                range.start(),
+                range.end(),
+                character
+            ));
+        }
+        Ok(Self {
+            character: character,
+            length: length,
+            start: start,
+            end: end,
+            others: others,
+            shapes: shapes,
+        })
+    }
+
+    fn random(&self, rng: &mut Rng) -> String {
+        let shape = self.shapes[rng.range(0, self.shapes.len() as i64) as usize];
+        let mut password: Vec<char> = (0..self.length)
+            .map(|_| self.others[rng.range(0, self.others.len() as i64) as usize])
+            .collect();
+        let mut placed = 0;
+        for (position, flag) in &[(self.start, shape.at_start), (self.end, shape.at_end)] {
+            if let (Some(position), true) = (position, flag) {
+                if password[*position] != self.character {
+                    password[*position] = self.character;
+                    placed += 1;
+                }
+            }
+        }
+        let mut free: Vec<usize> = (0..self.length)
+            .filter(|i| Some(*i) != self.start && Some(*i) != self.end)
+            .collect();
+        for i in 0..shape.count - placed {
+            let j = rng.range(i as i64, free.len() as i64) as usize;
+            free.swap(i, j);
+            password[free[i]] = self.character;
+        }
+        password.into_iter().collect()
+    }
+
+    // Every satisfying password in alphabetical order, up to `limit` of them.
+    fn enumerate(&self, limit: usize) -> Vec<String> {
+        let mut alphabet = self.others.clone();
+        alphabet.push(self.character);
+        alphabet.sort();
+        let mut found = vec![];
+        self.extend(&alphabet, &mut vec![], limit, &mut found);
+        found
+    }
+
+    fn extend(
+        &self,
+        alphabet: &[char],
+        prefix: &mut Vec<char>,
+        limit: usize,
+        found: &mut Vec<String>,
+    ) {
+        if found.len() >= limit || !self.can_complete(prefix) {
+            return;
+        }
+        if prefix.len() == self.length {
+            found.push(prefix.iter().collect());
+            return;
+        }
+        for c in alphabet {
+            prefix.push(*c);
+            self.extend(alphabet, prefix, limit, found);
+            prefix.pop();
+        }
+    }
+
+    // Whether some shape can still be reached from the prefix, which prunes the
+    // enumeration to branches that lead to at least one password.
+    fn can_complete(&self, prefix: &[char]) -> bool {
+        let depth = prefix.len();
+        let count = prefix.iter().filter(|c| **c == self.character).count();
+        self.shapes.iter().any(|shape| {
+            let mut pending = vec![];
+            for (position, flag) in &[(self.start, shape.at_start), (self.end, shape.at_end)] {
+                match position {
+                    Some(p) if *p < depth => {
+                        if (prefix[*p] == self.character) != *flag {
+                            return false;
+                        }
+                    }
+                    Some(p) if !pending.contains(&(*p, *flag)) => pending.push((*p, *flag)),
+                    _ => {}
+                }
+            }
+            let must = pending.iter().filter(|(_, flag)| *flag).count();
+            let cannot = pending.len() - must;
+            count + must <= shape.count && shape.count + cannot <= count + self.length - depth
+        })
+    }
+}
+
+fn parse_entry(line: &str) -> Result<(&str, &str), String> {
+    match line.splitn(2, ':').collect::<Vec<&str>>()[..] {
+        [rule, password] => Ok((rule, password.trim())),
+        _ => Err(format!("Found wrong entry {:?}", line)),
+    }
+}
+
+fn is_valid_password1(policy: CharPolicy, password: &str) -> bool {
     let character_count: i32 = password
         .chars()
         .filter(|c| *c == policy.1)
@@ -36,29 +626,374 @@ fn is_valid_password1(policy: Policy, password: &str) -> bool {
     return policy.0.contains(&character_count);
 }
 
-fn is_valid_password2(policy: Policy, password: &str) -> bool {
-    let character1 = password
-        .chars()
-        .nth((*policy.0.start() - 1) as usize)
-        .unwrap();
-    let character2 = password
-        .chars()
-        .nth((*policy.0.end() - 1) as usize)
-        .unwrap();
-    return (character1 == policy.1 && character2 != policy.1)
-        || (character2 == policy.1 && character1 != policy.1);
+fn is_valid_password2(policy: CharPolicy, password: &str) -> bool {
+    let character1 = char_at(password, *policy.0.start());
+    let character2 = char_at(password, *policy.0.end());
+    return (character1 == Some(policy.1)) != (character2 == Some(policy.1));
+}
+
+// Positions are 1-based; anything outside the password holds no character.
+fn char_at(password: &str, position: i32) -> Option<char> {
+    if position < 1 {
+        return None;
+    }
+    password.chars().nth((position - 1) as usize)
 }
 
-fn parse_policy(policy: &str) -> Policy {
-    match policy.split(" ").collect::<Vec<&str>>()[..] {
-        [count, character] => (parse_count(count), character.chars().next().unwrap()),
-        _ => panic!("Found wrong policy {:?}", policy),
+fn parse_policy(policy: &str) -> Result<CharPolicy, String> {
+    match policy.split_whitespace().collect::<Vec<&str>>()[..] {
+        [count, character] if character.chars().count() == 1 => {
+            Ok((parse_count(count)?, character.chars().next().unwrap()))
+        }
+        _ => Err(format!("Found wrong policy {:?}", policy)),
     }
 }
 
-fn parse_count(count: &str) -> RangeInclusive<i32> {
+fn parse_count(count: &str) -> Result<RangeInclusive<i32>, String> {
     match count.split("-").collect::<Vec<&str>>()[..] {
-        [start, end] => start.parse().unwrap()..=end.parse().unwrap(),
-        _ => panic!("Found wrong count {:?}", count),
+        [start, end] => match (start.parse(), end.parse()) {
+            (Ok(start), Ok(end)) => Ok(start..=end),
+            _ => Err(format!("Found wrong count {:?}", count)),
+        },
+        _ => Err(format!("Found wrong count {:?}", count)),
     }
 }
+
+fn parse_text(rule: &str) -> Result<String, String> {
+    match rule.trim() {
+        "" => Err("Found empty policy".to_string()),
+        text => Ok(text.to_string()),
+    }
+}
+
+// `check [--policy count-in-range]`: how many passwords satisfy their rule.
+fn run_check(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--policy"], &[])?;
+    let policy_name = args.value("--policy").unwrap_or("count-in-range");
+    println!("{}", count_valid_passwords(&inp, policy_name)?);
+    Ok(())
+}
+
+// `audit [--policy count-in-range] [--json]`: the verdict on every entry and why.
+fn run_audit(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--policy"], &["--json"])?;
+    let policy_name = args.value("--policy").unwrap_or("count-in-range");
+    let decisions = audit(&inp, policy_name)?;
+    if args.flag("--json") {
+        println!("{}", render_audit_json(policy_name, &decisions));
+    } else {
+        println!("{}", render_audit_text(policy_name, &decisions));
+    }
+    Ok(())
+}
+
+// `generate --rule "1-3 a" --length 8 [--target policy1|policy2|both|exactly-one]
+// [--count 10] [--seed n] [--alphabet abc] [--enumerate]`: passwords satisfying
+// the rule, random by default or all of them in alphabetical order.
+fn run_generate(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &[
+            "--rule",
+            "--length",
+            "--target",
+            "--count",
+            "--seed",
+            "--alphabet",
+        ],
+        &["--enumerate"],
+    )?;
+    let policy = parse_policy(args.value("--rule").ok_or("--rule is required")?)?;
+    let length = args.parsed("--length", 8)?;
+    let target_name = args.value("--target").unwrap_or("both");
+    let target = TARGETS
+        .iter()
+        .find(|(name, _)| *name == target_name)
+        .map(|(_, target)| *target)
+        .ok_or(format!(
+            "Unknown target {}, available: {:?}",
+            target_name,
+            TARGETS.iter().map(|(name, _)| *name).collect::<Vec<&str>>()
+        ))?;
+    let count = args.parsed("--count", 10)?;
+    let alphabet = args
+        .value("--alphabet")
+        .unwrap_or("abcdefghijklmnopqrstuvwxyz");
+    let generator = PasswordGenerator::new(policy, length, target, alphabet)?;
+    if args.flag("--enumerate") {
+        for password in generator.enumerate(count) {
+            println!("{}", password);
+        }
+        return Ok(());
+    }
+    let default_seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut rng = Rng::new(args.parsed("--seed", default_seed)?);
+    for _ in 0..count {
+        println!("{}", generator.random(&mut rng));
+    }
+    Ok(())
+}
+
+// `stream [file|-] [--policy count-in-range] [--threads 4] [--chunk-lines 100000]
+// [--max-errors 20]`: validates a dump of any size, printing running counts and
+// malformed lines to stderr and the final counts to stdout. The dump can also be
+// given as `--input <file|->`; without either it is read from stdin.
+fn run_stream(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &[
+            "--input",
+            "--policy",
+            "--threads",
+            "--chunk-lines",
+            "--max-errors",
+        ],
+        &[],
+    )?;
+    let path = match (args.value("--input"), &args.positional[..]) {
+        (path, []) => path,
+        (None, [path]) => Some(path.as_str()),
+        _ => return Err("Give one dump, as a file name, - or --input".to_string()),
+    };
+    let policy_name = args.value("--policy").unwrap_or("count-in-range");
+    let threads = args.parsed("--threads", 4)?;
+    let chunk_lines = args.parsed("--chunk-lines", 100_000)?;
+    let max_errors = args.parsed("--max-errors", 20)?;
+    let mut reported = 0;
+    let mut on_progress = |counts: &StreamCounts, errors: Vec<String>| {
+        for err in errors {
+            if reported < max_errors {
+                eprintln!("{}", err);
+            }
+            reported += 1;
+        }
+        eprintln!("{}", describe_counts(counts));
+    };
+    let totals = match path {
+        None | Some("-") => {
+            let stdin = io::stdin();
+            let reader = stdin.lock();
+            validate_stream(reader, policy_name, threads, chunk_lines, &mut on_progress)?
+        }
+        Some(path) => {
+            let file = File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+            let reader = BufReader::new(file);
+            validate_stream(reader, policy_name, threads, chunk_lines, &mut on_progress)?
+        }
+    };
+    println!("{}", describe_counts(&totals));
+    Ok(())
+}
+
+fn describe_counts(counts: &StreamCounts) -> String {
+    format!(
+        "{} lines: {} valid, {} invalid, {} malformed",
+        counts.lines, counts.valid, counts.invalid, counts.malformed
+    )
+}
+
+#[test]
+fn test_count_valid_passwords() {
+    assert_eq!(Ok(2), count_valid_passwords(TEST_INPUT, "count-in-range"));
+    assert_eq!(
+        Ok(1),
+        count_valid_passwords(TEST_INPUT, "exactly-one-position")
+    );
+    assert_eq!(
+        Ok(1),
+        count_valid_passwords(TEST_INPUT, "none-of-positions")
+    );
+    assert!(count_valid_passwords(TEST_INPUT, "unknown").is_err());
+    assert_eq!(
+        Ok("1".to_string()),
+        solve_with_policy(TEST_INPUT.to_string(), "none-of-positions")
+    );
+    assert!(count_valid_passwords("1-3: abcde", "count-in-range").is_err());
+    assert!(count_valid_passwords("1-x a: abcde", "count-in-range").is_err());
+}
+
+#[test]
+fn test_text_policies() {
+    let input = "abc: cabbage\nabc: bad\nbb: cabbage";
+    assert_eq!(Ok(2), count_valid_passwords(input, "must-contain-all-of"));
+    assert_eq!(Ok(2), count_valid_passwords(input, "forbidden-substring"));
+    assert!(count_valid_passwords(" : abc", "forbidden-substring").is_err());
+}
+
+#[test]
+fn test_audit() {
+    let decisions = audit(TEST_INPUT, "count-in-range").unwrap();
+    assert_eq!(
+        "1: 1-3 a abcde valid: character 'a' occurs 1 time, allowed 1-3
+2: 1-3 b cdefg invalid: character 'b' occurs 0 times, allowed 1-3
+3: 2-9 c ccccccccc valid: character 'c' occurs 9 times, allowed 2-9
+3 entries checked with count-in-range: 2 valid, 1 invalid",
+        render_audit_text("count-in-range", &decisions)
+    );
+    let explanations: Vec<String> = audit(TEST_INPUT, "exactly-one-position")
+        .unwrap()
+        .into_iter()
+        .map(|decision| decision.explanation)
+        .collect();
+    assert_eq!(
+        vec![
+            "only position 1 contains 'a'",
+            "neither position 1 nor 3 contains 'b'",
+            "positions 2 and 9 both contain 'c'"
+        ],
+        explanations
+    );
+    assert_eq!(
+        "contains forbidden 'bb' at position 3",
+        audit("bb: cabbage", "forbidden-substring").unwrap()[0].explanation
+    );
+    assert_eq!(
+        "missing 'c' of 'abc'",
+        audit("abc: bad", "must-contain-all-of").unwrap()[0].explanation
+    );
+    assert_eq!(
+        Err("Line 2: Found wrong policy \"1-3\"".to_string()),
+        audit("1-3 a: a\n1-3: b", "count-in-range").map(|_| ())
+    );
+}
+
+#[test]
+fn test_render_audit_json() {
+    let decisions = audit("1-3 a: abcde", "count-in-range").unwrap();
+    assert_eq!(
+        r#"{"policy_kind":"count-in-range","entries":[{"line":1,"policy":"1-3 a","password":"abcde","verdict":"valid","explanation":"character 'a' occurs 1 time, allowed 1-3"}],"summary":{"total":1,"valid":1,"invalid":0}}"#,
+        render_audit_json("count-in-range", &decisions)
+    );
+}
+
+#[test]
+fn test_validate_stream() {
+    let input =
+        format!("{}\n", TEST_INPUT).repeat(50) + "1-3 a abcde\n\n1-x b: bbb\r\n2-9 c: cc\r\n§: a\n";
+    let mut errors = vec![];
+    let mut progress = vec![];
+    let counts = validate_stream(
+        io::Cursor::new(input.into_bytes()),
+        "count-in-range",
+        3,
+        7,
+        &mut |counts, chunk_errors| {
+            progress.push(counts.lines);
+            errors.extend(chunk_errors);
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        StreamCounts {
+            lines: 154,
+            valid: 101,
+            invalid: 50,
+            malformed: 3,
+        },
+        counts
+    );
+    assert_eq!(Some(&154), progress.last());
+    errors.sort();
+    assert_eq!(
+        vec![
+            "Line 151: Found wrong entry \"1-3 a abcde\"",
+            "Line 153: Found wrong count \"1-x\"",
+            "Line 155: Found wrong policy \"§\""
+        ],
+        errors
+    );
+
+    let bytes = b"1-3 a: abcde\n\xff\xfe: a\n".to_vec();
+    let counts = validate_stream(
+        io::Cursor::new(bytes),
+        "count-in-range",
+        1,
+        1,
+        &mut |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(1, counts.valid);
+    assert_eq!(1, counts.malformed);
+    assert!(validate_stream(io::Cursor::new(vec![]), "unknown", 1, 1, &mut |_, _| {}).is_err());
+}
+
+#[allow(dead_code)]
+fn all_passwords(alphabet: &[char], length: usize) -> Vec<String> {
+    (0..length).fold(vec![String::new()], |passwords, _| {
+        passwords
+            .iter()
+            .flat_map(|password| alphabet.iter().map(move |c| format!("{}{}", password, c)))
+            .collect()
+    })
+}
+
+#[test]
+fn test_generated_passwords_satisfy_target() {
+    let mut rng = Rng::new(38);
+    for _ in 0..500 {
+        let start = rng.range(-1, 8) as i32;
+        let policy = (start..=rng.range(-1, 8) as i32, 'a');
+        let length = rng.range(0, 7) as usize;
+        let (_, target) = TARGETS[rng.range(0, TARGETS.len() as i64) as usize];
+        let generator = match PasswordGenerator::new(policy.clone(), length, target, "abc") {
+            Ok(generator) => generator,
+            Err(_) => continue,
+        };
+        for _ in 0..10 {
+            let password = generator.random(&mut rng);
+            assert_eq!(length, password.len());
+            assert!(
+                target.accepts(
+                    is_valid_password1(policy.clone(), &password),
+                    is_valid_password2(policy.clone(), &password)
+                ),
+                "{:?} {:?} {}",
+                policy,
+                target,
+                password
+            );
+        }
+    }
+}
+
+#[test]
+fn test_enumerated_passwords_match_brute_force() {
+    let alphabet = ['a', 'b', 'c'];
+    for length in 0..=5 {
+        for start in 0..=6 {
+            for end in 0..=6 {
+                for (_, target) in TARGETS {
+                    let policy = (start..=end, 'b');
+                    let expected: Vec<String> = all_passwords(&alphabet, length)
+                        .into_iter()
+                        .filter(|password| {
+                            target.accepts(
+                                is_valid_password1(policy.clone(), password),
+                                is_valid_password2(policy.clone(), password),
+                            )
+                        })
+                        .collect();
+                    let generator = PasswordGenerator::new(policy.clone(), length, *target, "abc");
+                    match generator {
+                        Ok(generator) => {
+                            assert_eq!(expected, generator.enumerate(usize::MAX))
+                        }
+                        Err(_) => assert!(expected.is_empty()),
+                    }
+                }
+            }
+        }
+    }
+    let generator = PasswordGenerator::new((1..=3, 'a'), 3, Target::Both, "ab").unwrap();
+    assert_eq!(vec!["aab", "abb", "baa", "bba"], generator.enumerate(10));
+    assert!(PasswordGenerator::new((1..=1, 'a'), 3, Target::Policy2, "ab").is_err());
+    assert!(PasswordGenerator::new((1..=3, 'a'), 3, Target::Both, "a").is_err());
+}
+
+#[allow(dead_code)]
+const TEST_INPUT: &str = "1-3 a: abcde
+1-3 b: cdefg
+2-9 c: ccccccccc";
@@ -1,28 +1,647 @@
+use crate::args::Args;
+use crate::days::Tool;
+use num::BigInt;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
 pub fn part1(inp: String) {
-    let map: Vec<Vec<char>> = inp.lines().map(|l| l.chars().collect()).collect();
-    println!("{}", count_trees(map, (1, 3)));
+    let map = parse_map(inp);
+    println!("{}", count_trees(&map, Slope::new(3, 1), false));
 }
 
 pub fn part2(inp: String) {
-    let map: Vec<Vec<char>> = inp.lines().map(|l| l.chars().collect()).collect();
-    let steps = [(1, 1), (1, 3), (1, 5), (1, 7), (2, 1)];
-    let prod: i64 = steps
+    let map = parse_map(inp);
+    let slopes = [
+        Slope::new(1, 1),
+        Slope::new(3, 1),
+        Slope::new(5, 1),
+        Slope::new(7, 1),
+        Slope::new(1, 2),
+    ];
+    let prod: i64 = slopes
         .iter()
-        .map(|step| count_trees(map.clone(), *step))
+        .map(|slope| count_trees(&map, *slope, false))
         .product();
     println!("{}", prod);
 }
 
-fn count_trees(map: Vec<Vec<char>>, step: (usize, usize)) -> i64 {
-    let x_len = map.len() as usize;
-    let y_len = map[0].len() as usize;
-    let mut pos = (0, 0);
-    let mut count = 0;
-    while pos.0 + step.0 < x_len {
-        pos = (pos.0 + step.0, (pos.1 + step.1) % y_len);
-        if map[pos.0][pos.1] == '#' {
-            count = count + 1;
+pub static TOOLS: &[(&str, Tool)] = &[
+    ("slopes", Tool::WithInput(run_slopes)),
+    ("optimise", Tool::WithInput(run_optimise)),
+    ("render", Tool::WithInput(run_render)),
+];
+
+type Map = Vec<Vec<char>>;
+
+// How far the toboggan moves per step; negative values move left and up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Slope {
+    right: i64,
+    down: i64,
+}
+
+impl Slope {
+    fn new(right: i64, down: i64) -> Self {
+        Self {
+            right: right,
+            down: down,
+        }
+    }
+}
+
+fn parse_map(inp: String) -> Map {
+    inp.lines().map(|l| l.chars().collect()).collect()
+}
+
+// `right,down`, e.g. `3,1` or `-1,2`.
+fn parse_slope(slope: &str) -> Result<Slope, String> {
+    match slope
+        .split(",")
+        .map(|n| n.trim().parse())
+        .collect::<Vec<_>>()[..]
+    {
+        [Ok(0), Ok(0)] => Err("The slope 0,0 never moves".to_string()),
+        [Ok(right), Ok(down)] => Ok(Slope::new(right, down)),
+        _ => Err(format!(
+            "Found wrong slope {:?}, expected right,down",
+            slope
+        )),
+    }
+}
+
+// The cells the toboggan lands on, as rows and unwrapped columns, leaving out
+// the start. The map always repeats to the left and right; without vertical
+// wrapping the run ends when it leaves the top or bottom. Either way it ends
+// when it is back at its start, since every later cell would repeat.
+fn path(map: &Map, slope: Slope, wrap_vertically: bool) -> Vec<(usize, i64)> {
+    let mut cells = vec![];
+    if map.is_empty() || map[0].is_empty() || slope == Slope::new(0, 0) {
+        return cells;
+    }
+    let height = map.len() as i64;
+    let width = map[0].len() as i64;
+    let (mut row, mut column) = (0, 0);
+    loop {
+        row += slope.down;
+        column += slope.right;
+        if !wrap_vertically && (row < 0 || row >= height) {
+            return cells;
+        }
+        let row = row.rem_euclid(height);
+        if row == 0 && column.rem_euclid(width) == 0 {
+            return cells;
+        }
+        cells.push((row as usize, column));
+    }
+}
+
+fn cell(map: &Map, row: usize, column: i64) -> char {
+    map[row][column.rem_euclid(map[row].len() as i64) as usize]
+}
+
+fn count_trees(map: &Map, slope: Slope, wrap_vertically: bool) -> i64 {
+    path(map, slope, wrap_vertically)
+        .into_iter()
+        .filter(|(row, column)| cell(map, *row, *column) == '#')
+        .count() as i64
+}
+
+// What landing on each kind of terrain costs; anything else is free. The
+// puzzle's legend only has trees, at a cost of 1 each.
+#[derive(Clone, Debug, PartialEq)]
+struct Legend {
+    weights: BTreeMap<char, i64>,
+}
+
+impl Legend {
+    fn trees() -> Self {
+        Self {
+            weights: vec![('#', 1)].into_iter().collect(),
+        }
+    }
+
+    // `char=weight` entries separated by commas or newlines, e.g. `#=1,^=5`.
+    fn parse(legend: &str) -> Result<Self, String> {
+        let mut weights = BTreeMap::new();
+        for entry in legend.split(|c| c == ',' || c == '\n') {
+            if entry.trim().is_empty() {
+                continue;
+            }
+            let mut chars = entry.trim().chars();
+            match (chars.next(), chars.next(), chars.as_str().trim().parse()) {
+                (Some(c), Some('='), Ok(weight)) => {
+                    weights.insert(c, weight);
+                }
+                _ => return Err(format!("Found wrong legend entry {:?}", entry)),
+            }
+        }
+        if weights.is_empty() {
+            return Err("The legend is empty".to_string());
+        }
+        Ok(Self { weights: weights })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Cost {
+    total: i64,
+    hits: BTreeMap<char, usize>,
+}
+
+impl Cost {
+    fn hit(&mut self, c: char, legend: &Legend) {
+        if let Some(weight) = legend.weights.get(&c) {
+            self.total += weight;
+            *self.hits.entry(c).or_insert(0) += 1;
+        }
+    }
+
+    fn describe(&self, legend: &Legend) -> String {
+        if *legend == Legend::trees() {
+            return format!("{} trees", self.total);
+        }
+        let hits: Vec<String> = legend
+            .weights
+            .keys()
+            .map(|c| format!("{} {}", self.hits.get(c).unwrap_or(&0), c))
+            .collect();
+        format!("cost {} ({})", self.total, hits.join(", "))
+    }
+}
+
+fn terrain_cost(map: &Map, slope: Slope, wrap_vertically: bool, legend: &Legend) -> Cost {
+    let mut cost = Cost::default();
+    for (row, column) in path(map, slope, wrap_vertically) {
+        cost.hit(cell(map, row, column), legend);
+    }
+    cost
+}
+
+// Markers for open cells and trees, one pair per slope, and the ANSI colour
+// each slope gets in colour mode.
+static MARKERS: &[(char, char)] = &[
+    ('O', 'X'),
+    ('P', 'Y'),
+    ('Q', 'Z'),
+    ('o', 'x'),
+    ('p', 'y'),
+    ('q', 'z'),
+];
+static COLOURS: &[u8] = &[31, 32, 33, 34, 35, 36];
+
+// The map with every cell a slope lands on marked, repeated to the left and
+// right as far as the paths go. Where paths cross, the first slope's marker
+// is shown.
+fn render_paths(map: &Map, slopes: &[Slope], wrap_vertically: bool, colour: bool) -> String {
+    let mut visited: HashMap<(usize, i64), usize> = HashMap::new();
+    let (mut min_column, mut max_column) =
+        (0, map.first().map(|row| row.len() as i64 - 1).unwrap_or(0));
+    for (i, slope) in slopes.iter().enumerate() {
+        for (row, column) in path(map, *slope, wrap_vertically) {
+            visited.entry((row, column)).or_insert(i);
+            min_column = min_column.min(column);
+            max_column = max_column.max(column);
+        }
+    }
+    let marker = |i: usize, tree: bool| {
+        let (open_marker, tree_marker) = MARKERS[i % MARKERS.len()];
+        let marker = if tree { tree_marker } else { open_marker };
+        if colour {
+            format!("\x1b[1;{}m{}\x1b[0m", COLOURS[i % COLOURS.len()], marker)
+        } else {
+            marker.to_string()
+        }
+    };
+    let mut lines: Vec<String> = map
+        .iter()
+        .enumerate()
+        .map(|(row, _)| {
+            (min_column..=max_column)
+                .map(|column| {
+                    let c = cell(map, row, column);
+                    match visited.get(&(row, column)) {
+                        Some(i) => marker(*i, c == '#'),
+                        None => c.to_string(),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    for (i, slope) in slopes.iter().enumerate() {
+        lines.push(format!(
+            "{}/{}: right {}, down {}, {} trees",
+            marker(i, false),
+            marker(i, true),
+            slope.right,
+            slope.down,
+            count_trees(map, *slope, wrap_vertically)
+        ));
+    }
+    lines.join("\n")
+}
+
+// The terrain from the legend per column, by row, built once per map and
+// shared by every slope that is ranked. Step k of a slope lands in column
+// k * right (mod width), so a column is only reached on the steps of one
+// residue class, if any: the query walks those steps down each reachable
+// column, which visits every step of the path once.
+struct TerrainIndex {
+    height: usize,
+    legend: Legend,
+    columns: Vec<Vec<Option<char>>>,
+}
+
+impl TerrainIndex {
+    fn new(map: &Map, legend: &Legend) -> Self {
+        let width = map.first().map(|row| row.len()).unwrap_or(0);
+        let mut columns = vec![vec![None; map.len()]; width];
+        for (row, line) in map.iter().enumerate() {
+            for (column, c) in line.iter().enumerate() {
+                if legend.weights.contains_key(c) {
+                    columns[column][row] = Some(*c);
+                }
+            }
+        }
+        Self {
+            height: map.len(),
+            legend: legend.clone(),
+            columns: columns,
+        }
+    }
+
+    // The same as `terrain_cost` without vertical wrapping, for downward slopes.
+    fn cost(&self, slope: Slope) -> Cost {
+        assert!(slope.down > 0, "Only downward slopes are indexed");
+        let width = self.columns.len() as i64;
+        let mut cost = Cost::default();
+        if width == 0 {
+            return cost;
+        }
+        let steps = self.steps(slope);
+        let right = slope.right.rem_euclid(width);
+        let (g, inverse, _) = extended_gcd(right, width);
+        let period = width / g;
+        for (column, rows) in self.columns.iter().enumerate() {
+            let column = column as i64;
+            if column % g != 0 {
+                continue;
+            }
+            // The first step that lands in this column; step 0 is the start.
+            let mut step = (column / g * inverse).rem_euclid(period);
+            if step == 0 {
+                step = period;
+            }
+            while step <= steps {
+                if let Some(c) = rows[(step * slope.down) as usize] {
+                    cost.hit(c, &self.legend);
+                }
+                step += period;
+            }
+        }
+        cost
+    }
+
+    fn steps(&self, slope: Slope) -> i64 {
+        (self.height as i64 - 1).max(0) / slope.down
+    }
+}
+
+// (gcd(a, b), x, y) with a * x + b * y = gcd(a, b).
+fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if a == 0 {
+        return (b, 0, 1);
+    }
+    let (g, x, y) = extended_gcd(b % a, a);
+    (g, y - b / a * x, x)
+}
+
+#[derive(Debug, PartialEq)]
+struct RankedSlope {
+    slope: Slope,
+    cost: Cost,
+    steps: i64,
+}
+
+impl RankedSlope {
+    fn squared_length(&self) -> i64 {
+        self.steps
+            * self.steps
+            * (self.slope.right * self.slope.right + self.slope.down * self.slope.down)
+    }
+}
+
+// Every downward slope right/down in lowest terms with steps of at most
+// `max_step` either way, cheapest first and then shortest path.
+fn rank_slopes(map: &Map, max_step: i64, legend: &Legend) -> Vec<RankedSlope> {
+    let index = TerrainIndex::new(map, legend);
+    let mut ranked = vec![];
+    for down in 1..=max_step {
+        for right in -max_step..=max_step {
+            if extended_gcd(right.abs(), down).0 != 1 {
+                continue;
+            }
+            let slope = Slope::new(right, down);
+            ranked.push(RankedSlope {
+                slope: slope,
+                cost: index.cost(slope),
+                steps: index.steps(slope),
+            });
+        }
+    }
+    ranked.sort_by_key(|ranked| {
+        (
+            ranked.cost.total,
+            ranked.squared_length(),
+            ranked.slope.down,
+            ranked.slope.right,
+        )
+    });
+    ranked
+}
+
+// `--legend "#=1,^=5"` or `--legend-file legend.txt`, trees only by default.
+fn legend_from_args(args: &Args) -> Result<Legend, String> {
+    match (args.value("--legend"), args.value("--legend-file")) {
+        (Some(legend), _) => Legend::parse(legend),
+        (None, Some(path)) => {
+            Legend::parse(&fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?)
+        }
+        (None, None) => Ok(Legend::trees()),
+    }
+}
+
+// `slopes [right,down ...] [--file slopes.txt] [--wrap-vertically] [--legend
+// ...]`: trees or terrain cost per slope and their product. The file has one
+// slope per line.
+fn run_slopes(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &["--file", "--legend", "--legend-file"],
+        &["--wrap-vertically"],
+    )?;
+    let legend = legend_from_args(&args)?;
+    let mut slopes = args
+        .positional
+        .iter()
+        .map(|slope| parse_slope(slope))
+        .collect::<Result<Vec<Slope>, String>>()?;
+    if let Some(path) = args.value("--file") {
+        let content = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            slopes.push(parse_slope(line)?);
+        }
+    }
+    if slopes.is_empty() {
+        return Err("Give at least one slope as right,down".to_string());
+    }
+    let map = parse_map(inp);
+    let mut product = BigInt::from(1);
+    for slope in slopes {
+        let cost = terrain_cost(&map, slope, args.flag("--wrap-vertically"), &legend);
+        println!(
+            "right {}, down {}: {}",
+            slope.right,
+            slope.down,
+            cost.describe(&legend)
+        );
+        product *= cost.total;
+    }
+    println!("product: {}", product);
+    Ok(())
+}
+
+// `optimise [--max-step 7] [--top 10] [--legend ...]`: the downward slopes
+// hitting the fewest trees, or with the lowest terrain cost.
+fn run_optimise(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &["--max-step", "--top", "--legend", "--legend-file"],
+        &[],
+    )?;
+    let legend = legend_from_args(&args)?;
+    let max_step = args.parsed("--max-step", 7)?;
+    if max_step < 1 {
+        return Err("--max-step needs to be at least 1".to_string());
+    }
+    for ranked in rank_slopes(&parse_map(inp), max_step, &legend)
+        .iter()
+        .take(args.parsed("--top", 10)?)
+    {
+        println!(
+            "right {}, down {}: {} in {} steps",
+            ranked.slope.right,
+            ranked.slope.down,
+            ranked.cost.describe(&legend),
+            ranked.steps
+        );
+    }
+    Ok(())
+}
+
+// `render [right,down ...] [--wrap-vertically] [--colour]`: the map with the
+// cells each slope lands on marked, 3,1 if no slope is given.
+fn run_render(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[], &["--wrap-vertically", "--colour"])?;
+    let mut slopes = args
+        .positional
+        .iter()
+        .map(|slope| parse_slope(slope))
+        .collect::<Result<Vec<Slope>, String>>()?;
+    if slopes.is_empty() {
+        slopes.push(Slope::new(3, 1));
+    }
+    println!(
+        "{}",
+        render_paths(
+            &parse_map(inp),
+            &slopes,
+            args.flag("--wrap-vertically"),
+            args.flag("--colour")
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn test_count_trees() {
+    let map = parse_map(TEST_INPUT.to_string());
+    let counts: Vec<i64> = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+        .iter()
+        .map(|(right, down)| count_trees(&map, Slope::new(*right, *down), false))
+        .collect();
+    assert_eq!(vec![2, 7, 3, 4, 2], counts);
+    assert_eq!(0, count_trees(&map, Slope::new(3, -1), false));
+    assert_eq!(0, count_trees(&map, Slope::new(0, 0), true));
+}
+
+#[test]
+fn test_count_trees_in_any_direction() {
+    let map = parse_map(TEST_INPUT.to_string());
+    // Mirrored around the start column, so moving left sees what moving right
+    // sees on the original map.
+    let mirrored: Map = map
+        .iter()
+        .map(|row| {
+            (0..row.len())
+                .map(|i| row[(row.len() - i) % row.len()])
+                .collect()
+        })
+        .collect();
+    for down in 1..=3 {
+        for right in 1..=7 {
+            assert_eq!(
+                count_trees(&map, Slope::new(right, down), false),
+                count_trees(&mirrored, Slope::new(-right, down), false)
+            );
+        }
+    }
+    // With vertical wrapping a run goes round one whole cycle, which the
+    // opposite slope walks backwards.
+    for down in -3..=3 {
+        for right in -7..=7 {
+            assert_eq!(
+                count_trees(&map, Slope::new(right, down), true),
+                count_trees(&map, Slope::new(-right, -down), true)
+            );
         }
     }
-    count
+    assert_eq!(Ok(Slope::new(-1, 2)), parse_slope("-1, 2"));
+    assert!(parse_slope("0,0").is_err());
+    assert!(parse_slope("1").is_err());
 }
+
+#[test]
+fn test_terrain_index_agrees_with_terrain_cost() {
+    let legend = Legend::parse("#=3,~=1,^=10").unwrap();
+    let mut rng = crate::rng::Rng::new(3);
+    let mut random_map = |height: usize, width: usize| -> Map {
+        (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| ['.', '#', '~', '^', '.'][rng.range(0, 5) as usize])
+                    .collect()
+            })
+            .collect()
+    };
+    let maps = vec![
+        parse_map(TEST_INPUT.to_string()),
+        parse_map(TEST_INPUT.replace("..", ".~").replace("#.#", "#^#")),
+        parse_map("#\n#\n.\n#".to_string()),
+        parse_map("#.#.#.".to_string()),
+        random_map(37, 12),
+        random_map(50, 7),
+    ];
+    for map in &maps {
+        let tree_index = TerrainIndex::new(map, &Legend::trees());
+        let index = TerrainIndex::new(map, &legend);
+        for down in 1..=12 {
+            for right in -25..=25 {
+                let slope = Slope::new(right, down);
+                assert_eq!(
+                    count_trees(map, slope, false),
+                    tree_index.cost(slope).total,
+                    "{:?} on {:?}",
+                    slope,
+                    map
+                );
+                assert_eq!(
+                    terrain_cost(map, slope, false, &legend),
+                    index.cost(slope),
+                    "{:?} on {:?}",
+                    slope,
+                    map
+                );
+                assert_eq!(
+                    path(map, slope, false).len() as i64,
+                    index.steps(slope),
+                    "{:?} on {:?}",
+                    slope,
+                    map
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rank_slopes() {
+    let map = parse_map(TEST_INPUT.to_string());
+    let ranked = rank_slopes(&map, 3, &Legend::trees());
+    assert_eq!(7 + 4 + 4, ranked.len());
+    assert!(ranked
+        .windows(2)
+        .all(|pair| (pair[0].cost.total, pair[0].squared_length())
+            <= (pair[1].cost.total, pair[1].squared_length())));
+    assert!(ranked.iter().all(|ranked| ranked.slope != Slope::new(2, 2)));
+    let best = &ranked[0];
+    assert_eq!(count_trees(&map, best.slope, false), best.cost.total);
+    assert!(ranked
+        .iter()
+        .all(|ranked| ranked.cost.total >= best.cost.total));
+
+    let weighted_map = parse_map(TEST_INPUT.replace("..", ".~").replace("#.#", "#^#"));
+    let legend = Legend::parse("#=3,~=1,^=10").unwrap();
+    for ranked in rank_slopes(&weighted_map, 3, &legend) {
+        assert_eq!(
+            terrain_cost(&weighted_map, ranked.slope, false, &legend),
+            ranked.cost
+        );
+    }
+}
+
+#[test]
+fn test_legend() {
+    let legend = Legend::parse("#=1, ^=5\n~=-2\n").unwrap();
+    assert_eq!(
+        vec![('#', 1), ('^', 5), ('~', -2)],
+        legend
+            .weights
+            .clone()
+            .into_iter()
+            .collect::<Vec<(char, i64)>>()
+    );
+    assert!(Legend::parse("#1").is_err());
+    assert!(Legend::parse("#=x").is_err());
+    assert!(Legend::parse(" , ").is_err());
+
+    let map = parse_map("...\n.^.\n..#\n~..".to_string());
+    let cost = terrain_cost(&map, Slope::new(1, 1), false, &legend);
+    assert_eq!(4, cost.total);
+    assert_eq!("cost 4 (1 #, 1 ^, 1 ~)", cost.describe(&legend));
+    assert_eq!(
+        "1 trees",
+        terrain_cost(&map, Slope::new(1, 1), false, &Legend::trees()).describe(&Legend::trees())
+    );
+    let ranked = rank_slopes(&map, 1, &legend);
+    assert_eq!(Slope::new(0, 1), ranked[0].slope);
+    assert_eq!(-2, ranked[0].cost.total);
+}
+
+#[test]
+fn test_render_paths() {
+    let map = parse_map("..#\n#..\n.#.\n..#".to_string());
+    let slopes = [Slope::new(1, 1), Slope::new(-1, 1)];
+    assert_eq!(
+        "..#..#.
+#.P#O.#
+.Y..#O.
+P.#..#O
+O/X: right 1, down 1, 0 trees
+P/Y: right -1, down 1, 1 trees",
+        render_paths(&map, &slopes, false, false)
+    );
+    assert!(render_paths(&map, &slopes, false, true).contains("\x1b[1;32mY\x1b[0m"));
+}
+
+#[allow(dead_code)]
+const TEST_INPUT: &str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
@@ -1,128 +1,1443 @@
-use phf::{phf_map, phf_set};
+use crate::args::Args;
+use crate::days::Tool;
+use crate::json::{self, Value};
+use crate::records;
+use crate::toml;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::iter::FromIterator;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
 use std::ops::RangeInclusive;
 
 pub fn part1(inp: String) {
-    println!("{}", count_valid_passport(inp, is_valid1));
+    let schema = Schema::default();
+    match count_valid_passport(&inp, |passport| schema.has_required_fields(passport)) {
+        Ok(count) => println!("{}", count),
+        Err(err) => panic!("{}", err),
+    }
 }
 
 pub fn part2(inp: String) {
-    println!("{}", count_valid_passport(inp, is_valid2));
+    match count_valid(&inp, &Schema::default()) {
+        Ok(count) => println!("{}", count),
+        Err(err) => panic!("{}", err),
+    }
 }
 
-fn count_valid_passport(inp: String, is_valid: fn(Vec<&str>) -> bool) -> usize {
-    return inp
-        .split("\n\n")
-        .filter(|passport| is_valid(passport.split(['\n', ' '].as_ref()).collect()))
-        .count();
+pub static TOOLS: &[(&str, Tool)] = &[
+    ("check", Tool::WithInput(run_check)),
+    ("schema", Tool::Standalone(run_schema)),
+    ("report", Tool::WithInput(run_report)),
+    ("export", Tool::WithInput(run_export)),
+    ("import", Tool::WithInput(run_import)),
+];
+
+fn count_valid_passport<F: Fn(&HashMap<&str, &str>) -> bool>(
+    inp: &str,
+    is_valid: F,
+) -> Result<usize, String> {
+    let mut count = 0;
+    for section in records::sections(inp) {
+        if is_valid(&parse_passport(&section)?) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+// Passports that are valid, including the rules that span the batch.
+fn count_valid(inp: &str, schema: &Schema) -> Result<usize, String> {
+    Ok(report(inp, schema)?
+        .iter()
+        .filter(|report| report.is_valid())
+        .count())
+}
+
+// The puzzle's rules. A schema lists every field with its type; fields are
+// required unless they say `required = false`, and fields it does not list
+// are ignored.
+const DEFAULT_SCHEMA: &str = r#"[fields.byr]
+type = "int"
+min = 1920
+max = 2002
+
+[fields.iyr]
+type = "int"
+min = 2010
+max = 2020
+
+[fields.eyr]
+type = "int"
+min = 2020
+max = 2030
+
+[fields.hgt]
+type = "measure"
+units = { cm = [150, 193], in = [59, 76] }
+
+[fields.hcl]
+type = "regex"
+pattern = "^#[0-9a-f]{6}$"
+
+[fields.ecl]
+type = "enum"
+values = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]
+
+[fields.pid]
+type = "int"
+min = 1
+max = 999999999
+digits = 9
+
+[fields.cid]
+type = "any"
+required = false
+"#;
+
+// Rules that are not part of the puzzle, added to `DEFAULT_SCHEMA` by `schema
+// --example`. Rules span fields: `order` needs its fields' numbers ascending
+// (strictly with `strict = true`) and `unique` needs their values to differ
+// between passports, so it only applies when a whole batch is checked.
+const EXAMPLE_RULES: &str = r#"
+[rules.born-before-issue]
+type = "order"
+fields = ["byr", "iyr"]
+strict = true
+
+[rules.issued-before-expiry]
+type = "order"
+fields = ["iyr", "eyr"]
+
+[rules.unique-pid]
+type = "unique"
+fields = ["pid"]
+"#;
+
+enum FieldType {
+    Any,
+    Int {
+        range: RangeInclusive<i64>,
+        digits: Option<usize>,
+    },
+    Regex(Regex),
+    Enum(Vec<String>),
+    // A number directly followed by one of the units, each with its own range.
+    Measure(Vec<(String, RangeInclusive<i64>)>),
+}
+
+// A present field whose value breaks its rule.
+#[derive(Debug, PartialEq)]
+struct FieldError {
+    field: String,
+    value: String,
+    reason: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={} {}", self.field, self.value, self.reason)
+    }
+}
+
+// A broken rule and the fields it covers.
+#[derive(Clone, Debug, PartialEq)]
+struct RuleError {
+    rule: String,
+    fields: Vec<String>,
+    reason: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}",
+            self.rule,
+            self.fields.join(", "),
+            self.reason
+        )
+    }
+}
+
+enum RuleType {
+    Order { strict: bool },
+    Unique,
+}
+
+struct Rule {
+    name: String,
+    fields: Vec<String>,
+    rule_type: RuleType,
+}
+
+impl Rule {
+    // Order rules only compare fields that are present and numbers; the field
+    // rules already report the others.
+    fn check(&self, passport: &HashMap<&str, &str>) -> Option<RuleError> {
+        let strict = match self.rule_type {
+            RuleType::Order { strict } => strict,
+            RuleType::Unique => return None,
+        };
+        let values: Vec<(&String, &str, i64)> = self
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let value = passport.get(field.as_str())?;
+                Some((field, *value, value.parse().ok()?))
+            })
+            .collect();
+        if values.len() < self.fields.len() {
+            return None;
+        }
+        values
+            .windows(2)
+            .find(|pair| match strict {
+                true => pair[0].2 >= pair[1].2,
+                false => pair[0].2 > pair[1].2,
+            })
+            .map(|pair| RuleError {
+                rule: self.name.clone(),
+                fields: self.fields.clone(),
+                reason: format!(
+                    "{}={} is not {} {}={}",
+                    pair[0].0,
+                    pair[0].1,
+                    if strict { "before" } else { "at most" },
+                    pair[1].0,
+                    pair[1].1
+                ),
+            })
+    }
+
+    // For each passport, the unique rule it breaks by sharing its values.
+    fn check_batch(&self, passports: &[HashMap<&str, &str>]) -> Vec<Option<RuleError>> {
+        let keys: Vec<Option<Vec<&str>>> = passports
+            .iter()
+            .map(|passport| match self.rule_type {
+                RuleType::Unique => self
+                    .fields
+                    .iter()
+                    .map(|field| passport.get(field.as_str()).cloned())
+                    .collect(),
+                RuleType::Order { .. } => None,
+            })
+            .collect();
+        let mut passports_by_key: HashMap<&Vec<&str>, Vec<usize>> = HashMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            if let Some(key) = key {
+                passports_by_key.entry(key).or_insert(vec![]).push(i);
+            }
+        }
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let others: Vec<String> = passports_by_key[key.as_ref()?]
+                    .iter()
+                    .filter(|j| **j != i)
+                    .map(|j| (j + 1).to_string())
+                    .collect();
+                if others.is_empty() {
+                    return None;
+                }
+                let values: Vec<String> = self
+                    .fields
+                    .iter()
+                    .zip(key.as_ref()?)
+                    .map(|(field, value)| format!("{}={}", field, value))
+                    .collect();
+                Some(RuleError {
+                    rule: self.name.clone(),
+                    fields: self.fields.clone(),
+                    reason: format!(
+                        "{} also in passport {}",
+                        values.join(" "),
+                        others.join(", ")
+                    ),
+                })
+            })
+            .collect()
+    }
+}
+
+struct FieldRule {
+    name: String,
+    required: bool,
+    field_type: FieldType,
+}
+
+impl FieldRule {
+    // Why the value breaks the rule, e.g. "hgt=190in out of 59..76".
+    fn check(&self, value: &str) -> Result<(), FieldError> {
+        let reason = |why: String| {
+            Err(FieldError {
+                field: self.name.clone(),
+                value: value.to_string(),
+                reason: why,
+            })
+        };
+        match &self.field_type {
+            FieldType::Any => Ok(()),
+            FieldType::Int { range, digits } => {
+                if let Some(digits) = digits {
+                    if value.len() != *digits || !value.chars().all(|c| c.is_ascii_digit()) {
+                        return reason(format!("does not have {} digits", digits));
+                    }
+                }
+                match value.parse::<i64>() {
+                    Ok(n) if range.contains(&n) => Ok(()),
+                    Ok(_) => reason(format!("out of {}..{}", range.start(), range.end())),
+                    Err(_) => reason("is not a number".to_string()),
+                }
+            }
+            FieldType::Regex(regex) if regex.is_match(value) => Ok(()),
+            FieldType::Regex(regex) => reason(format!("does not match {}", regex.as_str())),
+            FieldType::Enum(values) if values.iter().any(|v| v == value) => Ok(()),
+            FieldType::Enum(values) => reason(format!("is not one of {}", values.join(", "))),
+            FieldType::Measure(units) => {
+                let digits_end = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(digits_end);
+                let units_list = || {
+                    units
+                        .iter()
+                        .map(|(unit, _)| unit.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                };
+                match (
+                    number.parse::<i64>(),
+                    units.iter().find(|(name, _)| name == unit),
+                ) {
+                    (Ok(n), Some((_, range))) if range.contains(&n) => Ok(()),
+                    (Ok(_), Some((_, range))) => {
+                        reason(format!("out of {}..{}", range.start(), range.end()))
+                    }
+                    _ => reason(format!("is not a number in {}", units_list())),
+                }
+            }
+        }
+    }
+}
+
+struct Schema {
+    fields: Vec<FieldRule>,
+    rules: Vec<Rule>,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::parse(DEFAULT_SCHEMA, false).unwrap()
+    }
 }
 
-static REQUIRED_FIELDS: phf::Set<&str> =
-    phf_set! { "byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid" };
+impl Schema {
+    fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Self::parse(&content, path.ends_with(".json")).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    fn parse(content: &str, is_json: bool) -> Result<Self, String> {
+        let document = if is_json {
+            json::parse(content)?
+        } else {
+            toml::parse(content)?
+        };
+        let fields = document
+            .get("fields")
+            .and_then(|fields| fields.as_object())
+            .ok_or("The schema needs a table of fields")?;
+        let fields = fields
+            .iter()
+            .map(|(name, rule)| {
+                Self::parse_field(name, rule).map_err(|err| format!("Field {}: {}", name, err))
+            })
+            .collect::<Result<Vec<FieldRule>, String>>()?;
+        let rules = match document.get("rules") {
+            Some(rules) => rules
+                .as_object()
+                .ok_or("The schema's rules need to be a table")?
+                .iter()
+                .map(|(name, rule)| {
+                    Self::parse_rule(name, rule, &fields)
+                        .map_err(|err| format!("Rule {}: {}", name, err))
+                })
+                .collect::<Result<Vec<Rule>, String>>()?,
+            None => vec![],
+        };
+        Ok(Self {
+            fields: fields,
+            rules: rules,
+        })
+    }
+
+    fn parse_rule(name: &str, rule: &Value, fields: &[FieldRule]) -> Result<Rule, String> {
+        let rule_fields: Vec<String> = rule
+            .get("fields")
+            .and_then(|fields| fields.as_array())
+            .and_then(|fields| {
+                fields
+                    .iter()
+                    .map(|field| field.as_str().map(|field| field.to_string()))
+                    .collect()
+            })
+            .ok_or("needs a list of fields")?;
+        if let Some(unknown) = rule_fields
+            .iter()
+            .find(|name| !fields.iter().any(|field| field.name == **name))
+        {
+            return Err(format!("unknown field {}", unknown));
+        }
+        let rule_type = match rule.get("type").and_then(|t| t.as_str()) {
+            Some("order") if rule_fields.len() >= 2 => RuleType::Order {
+                strict: rule
+                    .get("strict")
+                    .map(|strict| strict.as_bool().ok_or("strict needs true or false"))
+                    .unwrap_or(Ok(false))?,
+            },
+            Some("order") => return Err("needs at least two fields to order".to_string()),
+            Some("unique") if !rule_fields.is_empty() => RuleType::Unique,
+            Some("unique") => return Err("needs at least one field".to_string()),
+            other => return Err(format!("unknown type {:?}, use order or unique", other)),
+        };
+        Ok(Rule {
+            name: name.to_string(),
+            fields: rule_fields,
+            rule_type: rule_type,
+        })
+    }
+
+    fn parse_field(name: &str, rule: &Value) -> Result<FieldRule, String> {
+        let int = |key: &str| {
+            rule.get(key)
+                .and_then(|value| value.as_i64())
+                .ok_or(format!("needs an integer {}", key))
+        };
+        let field_type = match rule.get("type").and_then(|t| t.as_str()) {
+            Some("any") => FieldType::Any,
+            Some("int") => FieldType::Int {
+                range: int("min")?..=int("max")?,
+                digits: match rule.get("digits") {
+                    Some(_) => Some(int("digits")? as usize),
+                    None => None,
+                },
+            },
+            Some("regex") => {
+                let pattern = rule
+                    .get("pattern")
+                    .and_then(|pattern| pattern.as_str())
+                    .ok_or("needs a pattern")?;
+                FieldType::Regex(Regex::new(pattern).map_err(|err| err.to_string())?)
+            }
+            Some("enum") => FieldType::Enum(
+                rule.get("values")
+                    .and_then(|values| values.as_array())
+                    .and_then(|values| {
+                        values
+                            .iter()
+                            .map(|value| value.as_str().map(|value| value.to_string()))
+                            .collect()
+                    })
+                    .ok_or("needs a list of string values")?,
+            ),
+            Some("measure") => FieldType::Measure(
+                rule.get("units")
+                    .and_then(|units| units.as_object())
+                    .and_then(|units| {
+                        units
+                            .iter()
+                            .map(|(unit, range)| match range.as_array().map(|r| &r[..]) {
+                                Some([min, max]) => {
+                                    Some((unit.clone(), min.as_i64()?..=max.as_i64()?))
+                                }
+                                _ => None,
+                            })
+                            .collect()
+                    })
+                    .ok_or("needs units with [min, max] ranges")?,
+            ),
+            other => {
+                return Err(format!(
+                    "unknown type {:?}, use any, int, regex, enum or measure",
+                    other
+                ))
+            }
+        };
+        Ok(FieldRule {
+            name: name.to_string(),
+            required: rule
+                .get("required")
+                .map(|required| required.as_bool().ok_or("required needs true or false"))
+                .unwrap_or(Ok(true))?,
+            field_type: field_type,
+        })
+    }
+
+    fn has_required_fields(&self, passport: &HashMap<&str, &str>) -> bool {
+        self.fields
+            .iter()
+            .all(|field| !field.required || passport.contains_key(field.name.as_str()))
+    }
+
+    fn broken_rules(&self, passport: &HashMap<&str, &str>) -> Vec<RuleError> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.check(passport))
+            .collect()
+    }
 
-fn is_valid1(passport: Vec<&str>) -> bool {
-    let required_fields: HashSet<&str> = HashSet::from_iter(REQUIRED_FIELDS.iter().cloned());
-    let passport_fields = passport
+    // The missing required fields and every present field that is invalid.
+    fn violations(&self, passport: &HashMap<&str, &str>) -> (Vec<String>, Vec<FieldError>) {
+        let mut missing = vec![];
+        let mut invalid = vec![];
+        for field in &self.fields {
+            match passport.get(field.name.as_str()) {
+                Some(value) => {
+                    if let Err(err) = field.check(value) {
+                        invalid.push(err);
+                    }
+                }
+                None if field.required => missing.push(field.name.clone()),
+                None => {}
+            }
+        }
+        (missing, invalid)
+    }
+
+    fn field(&self, name: &str) -> Option<&FieldRule> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+// A passport's `key:value` entries in the order they were written.
+fn parse_fields<'a>(section: &records::Section<'a>) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let mut fields = vec![];
+    for (i, line) in section.lines.iter().enumerate() {
+        for entry in line.split_whitespace() {
+            match entry.split(":").collect::<Vec<&str>>()[..] {
+                [key, value] => fields.push((key, value)),
+                _ => {
+                    return Err(format!(
+                        "Line {}: expected key:value, found {:?}",
+                        section.line + i,
+                        entry
+                    ))
+                }
+            }
+        }
+    }
+    Ok(fields)
+}
+
+fn parse_passport<'a>(section: &records::Section<'a>) -> Result<HashMap<&'a str, &'a str>, String> {
+    Ok(parse_fields(section)?.into_iter().collect())
+}
+
+struct PassportReport {
+    number: usize,
+    line: usize,
+    missing: Vec<String>,
+    invalid: Vec<FieldError>,
+    broken: Vec<RuleError>,
+}
+
+impl PassportReport {
+    fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty() && self.broken.is_empty()
+    }
+
+    // What the summary counts, e.g. "missing hgt", "hgt out of 59..76" or
+    // "broke unique-pid".
+    fn reasons(&self) -> Vec<String> {
+        self.missing
+            .iter()
+            .map(|field| format!("missing {}", field))
+            .chain(
+                self.invalid
+                    .iter()
+                    .map(|err| format!("{} {}", err.field, err.reason)),
+            )
+            .chain(self.broken.iter().map(|err| format!("broke {}", err.rule)))
+            .collect()
+    }
+}
+
+// Every passport checked against the schema, including the rules that span
+// the batch.
+fn report(inp: &str, schema: &Schema) -> Result<Vec<PassportReport>, String> {
+    let sections = records::sections(inp);
+    let passports: Vec<HashMap<&str, &str>> = sections
+        .iter()
+        .map(parse_passport)
+        .collect::<Result<_, String>>()?;
+    let batch_errors: Vec<Vec<Option<RuleError>>> = schema
+        .rules
         .iter()
-        .map(|entry| entry.split(":").next().unwrap())
+        .map(|rule| rule.check_batch(&passports))
         .collect();
-    required_fields.difference(&passport_fields).count() == 0
-}
-
-lazy_static! {
-    static ref HGT_REGEX: Regex = Regex::new(r"^(\d+)cm|(\d+)in$").unwrap();
-    static ref HCL_REGEX: Regex = Regex::new(r"^\#[0-9a-f]{6}$").unwrap();
-}
-
-static VALIDATORS: phf::Map<&str, fn(&str) -> bool> = phf_map! {
-    "byr" => |s| validate_number_between(s, 1920..=2002),
-    "iyr" => |s| validate_number_between(s, 2010..=2020),
-    "eyr" => |s| validate_number_between(s, 2020..=2030),
-    "hgt" => validate_hgt,
-    "hcl" => validate_hcl,
-    "ecl" => |s| phf_set!{"amb", "blu", "brn", "gry", "grn", "hzl", "oth"}.contains(s),
-    "pid" => validate_pid,
-};
-
-fn is_valid2(raw_passport: Vec<&str>) -> bool {
-    let passport = parse_passport(raw_passport);
-    return VALIDATORS
-        .entries()
-        .all(|(key, is_valid)| match passport.get(key) {
-            Some(value) => is_valid(value),
-            None => false,
+    let mut reports = vec![];
+    for (i, section) in sections.iter().enumerate() {
+        let (missing, invalid) = schema.violations(&passports[i]);
+        let mut broken = schema.broken_rules(&passports[i]);
+        for errors in &batch_errors {
+            broken.extend(errors[i].clone());
+        }
+        reports.push(PassportReport {
+            number: i + 1,
+            line: section.line,
+            missing: missing,
+            invalid: invalid,
+            broken: broken,
         });
+    }
+    Ok(reports)
+}
+
+// How often each reason occurs, most frequent first.
+fn count_reasons(reports: &[PassportReport]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for reason in reports.iter().flat_map(|report| report.reasons()) {
+        *counts.entry(reason).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(reason1, count1), (reason2, count2)| {
+        count2.cmp(count1).then(reason1.cmp(reason2))
+    });
+    counts
+}
+
+fn render_report_text(reports: &[PassportReport]) -> String {
+    let mut lines: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let mut problems: Vec<String> = vec![];
+            if !report.missing.is_empty() {
+                problems.push(format!("missing {}", report.missing.join(", ")));
+            }
+            problems.extend(report.invalid.iter().map(|err| err.to_string()));
+            problems.extend(report.broken.iter().map(|err| err.to_string()));
+            format!(
+                "passport {} (line {}): {}",
+                report.number,
+                report.line,
+                if problems.is_empty() {
+                    "valid".to_string()
+                } else {
+                    problems.join("; ")
+                }
+            )
+        })
+        .collect();
+    let valid_count = reports.iter().filter(|report| report.is_valid()).count();
+    lines.push(format!(
+        "{} passports: {} valid, {} invalid",
+        reports.len(),
+        valid_count,
+        reports.len() - valid_count
+    ));
+    for (reason, count) in count_reasons(reports) {
+        lines.push(format!("{}: {}", reason, count));
+    }
+    lines.join("\n")
 }
 
-fn parse_passport(passport: Vec<&str>) -> HashMap<&str, &str> {
-    return passport
+fn render_report_json(reports: &[PassportReport]) -> String {
+    let passports: Vec<String> = reports
         .iter()
-        .map(|entry| match entry.split(":").collect::<Vec<&str>>()[..] {
-            [key, value] => (key, value),
-            _ => panic!("Invalid key-value {:?}", entry),
+        .map(|report| {
+            let missing: Vec<String> = report
+                .missing
+                .iter()
+                .map(|field| json::string(field))
+                .collect();
+            let invalid: Vec<String> = report
+                .invalid
+                .iter()
+                .map(|err| {
+                    json::object(&[
+                        ("field", json::string(&err.field)),
+                        ("value", json::string(&err.value)),
+                        ("reason", json::string(&err.reason)),
+                    ])
+                })
+                .collect();
+            let broken: Vec<String> = report
+                .broken
+                .iter()
+                .map(|err| {
+                    let fields: Vec<String> =
+                        err.fields.iter().map(|field| json::string(field)).collect();
+                    json::object(&[
+                        ("rule", json::string(&err.rule)),
+                        ("fields", json::array(&fields)),
+                        ("reason", json::string(&err.reason)),
+                    ])
+                })
+                .collect();
+            json::object(&[
+                ("passport", report.number.to_string()),
+                ("line", report.line.to_string()),
+                ("valid", report.is_valid().to_string()),
+                ("missing", json::array(&missing)),
+                ("invalid", json::array(&invalid)),
+                ("rules", json::array(&broken)),
+            ])
         })
         .collect();
+    let reasons: Vec<(String, usize)> = count_reasons(reports);
+    let valid_count = reports.iter().filter(|report| report.is_valid()).count();
+    json::object(&[
+        ("passports", json::array(&passports)),
+        (
+            "summary",
+            json::object(&[
+                ("total", reports.len().to_string()),
+                ("valid", valid_count.to_string()),
+                ("invalid", (reports.len() - valid_count).to_string()),
+                (
+                    "reasons",
+                    json::object(
+                        &reasons
+                            .iter()
+                            .map(|(reason, count)| (reason.as_str(), count.to_string()))
+                            .collect::<Vec<(&str, String)>>(),
+                    ),
+                ),
+            ]),
+        ),
+    ])
 }
 
-fn validate_number_between(s: &str, r: RangeInclusive<i64>) -> bool {
-    s.parse::<i64>()
-        .map(|x| if r.contains(&x) { true } else { false })
-        .unwrap_or(false)
+// A field value typed by its rule. Values that do not fit their type, or that
+// would not be written back the same, stay text so nothing is lost.
+#[derive(Debug, PartialEq)]
+enum TypedValue {
+    Int(i64),
+    Measure(i64, String),
+    Text(String),
 }
 
-fn validate_hgt(s: &str) -> bool {
-    let captures = HGT_REGEX.captures(s);
-    return captures
-        .and_then(|c| {
-            let cm_validation = c.get(1).and_then(|n| {
-                let n = n.as_str().parse::<i32>().unwrap();
-                if n >= 150 && n <= 193 {
-                    Some(true)
-                } else {
-                    None
+impl TypedValue {
+    fn new(schema: &Schema, field: &str, value: &str) -> Self {
+        let field_type = schema.field(field).map(|rule| &rule.field_type);
+        match field_type {
+            Some(FieldType::Int { .. }) => match value.parse::<i64>() {
+                Ok(n) if n.to_string() == value => return TypedValue::Int(n),
+                _ => {}
+            },
+            Some(FieldType::Measure(_)) => {
+                let digits_end = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(digits_end);
+                match number.parse::<i64>() {
+                    Ok(n) if n.to_string() == number => {
+                        return TypedValue::Measure(n, unit.to_string())
+                    }
+                    _ => {}
                 }
-            });
-            let in_validation = c.get(2).and_then(|n| {
-                let n = n.as_str().parse::<i32>().unwrap();
-                if n >= 59 && n <= 76 {
-                    Some(true)
-                } else {
-                    None
-                }
-            });
-            cm_validation.or(in_validation)
+            }
+            _ => {}
+        }
+        TypedValue::Text(value.to_string())
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            TypedValue::Int(n) => n.to_string(),
+            TypedValue::Measure(n, unit) => {
+                json::object(&[("value", n.to_string()), ("unit", json::string(unit))])
+            }
+            TypedValue::Text(text) => json::string(text),
+        }
+    }
+
+    fn from_json(value: &Value) -> Result<String, String> {
+        match value {
+            Value::String(text) => Ok(text.clone()),
+            Value::Integer(n) => Ok(n.to_string()),
+            Value::Number(_) => Err(format!("{:?} is not a whole number", value)),
+            Value::Object(_) => match (
+                value.get("value").and_then(|n| n.as_i64()),
+                value.get("unit").and_then(|unit| unit.as_str()),
+            ) {
+                (Some(n), Some(unit)) => Ok(format!("{}{}", n, unit)),
+                _ => Err(format!("{:?} needs a value and a unit", value)),
+            },
+            _ => Err(format!("{:?} is not a field value", value)),
+        }
+    }
+}
+
+fn parse_batch(inp: &str) -> Result<Vec<Vec<(&str, &str)>>, String> {
+    records::sections(inp).iter().map(parse_fields).collect()
+}
+
+// Fails on fields the batch format cannot hold, so imports read back the same.
+fn write_batch(passports: &[Vec<(String, String)>]) -> Result<String, String> {
+    let mut written = vec![];
+    for (i, passport) in passports.iter().enumerate() {
+        let mut entries = vec![];
+        for (key, value) in passport {
+            if format!("{}{}", key, value).contains(|c: char| c == ':' || c.is_whitespace()) {
+                return Err(format!(
+                    "Passport {}: {:?}={:?} cannot be written as key:value",
+                    i + 1,
+                    key,
+                    value
+                ));
+            }
+            entries.push(format!("{}:{}", key, value));
+        }
+        written.push(entries.join(" "));
+    }
+    Ok(written.join("\n\n"))
+}
+
+fn export_jsonl(inp: &str, schema: &Schema) -> Result<String, String> {
+    Ok(parse_batch(inp)?
+        .iter()
+        .map(|passport| {
+            let fields: Vec<(&str, String)> = passport
+                .iter()
+                .map(|(key, value)| (*key, TypedValue::new(schema, key, value).to_json()))
+                .collect();
+            json::object(&fields) + "\n"
+        })
+        .collect())
+}
+
+fn import_jsonl(inp: &str) -> Result<String, String> {
+    let mut passports = vec![];
+    for (i, line) in inp.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = json::parse(line)
+            .and_then(|passport| {
+                passport
+                    .as_object()
+                    .ok_or("A passport needs to be an object".to_string())?
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), TypedValue::from_json(value)?)))
+                    .collect::<Result<Vec<(String, String)>, String>>()
+            })
+            .map_err(|err| format!("Line {}: {}", i + 1, err))?;
+        passports.push(fields);
+    }
+    write_batch(&passports)
+}
+
+// The schema's fields in schema order, then any others alphabetically. Measures
+// take two columns, `<field>_value` and `<field>_unit`.
+fn csv_columns(passports: &[Vec<(&str, &str)>], schema: &Schema) -> Vec<(String, bool)> {
+    let mut names: Vec<&str> = schema
+        .fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect();
+    let mut others: Vec<&str> = passports
+        .iter()
+        .flat_map(|passport| passport.iter().map(|(key, _)| *key))
+        .filter(|key| schema.field(key).is_none())
+        .collect();
+    others.sort();
+    others.dedup();
+    names.extend(others);
+    names
+        .into_iter()
+        .map(|name| {
+            let is_measure = match schema.field(name).map(|rule| &rule.field_type) {
+                Some(FieldType::Measure(_)) => true,
+                _ => false,
+            };
+            (name.to_string(), is_measure)
+        })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Missing fields are empty cells and empty values are `""`, so that import
+// can tell them apart.
+fn export_csv(inp: &str, schema: &Schema) -> Result<String, String> {
+    let passports = parse_batch(inp)?;
+    let columns = csv_columns(&passports, schema);
+    let header: Vec<String> = columns
+        .iter()
+        .flat_map(|(name, is_measure)| match is_measure {
+            true => vec![format!("{}_value", name), format!("{}_unit", name)],
+            false => vec![name.clone()],
         })
-        .unwrap_or(false);
+        .collect();
+    let mut lines = vec![header.join(",")];
+    for passport in passports {
+        let row: Vec<String> = columns
+            .iter()
+            .flat_map(|(name, is_measure)| {
+                let value = passport
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| TypedValue::new(schema, name, value));
+                match (is_measure, value) {
+                    (true, Some(TypedValue::Measure(n, unit))) => {
+                        vec![
+                            Some(n.to_string()),
+                            Some(unit).filter(|unit| !unit.is_empty()),
+                        ]
+                    }
+                    (true, Some(TypedValue::Text(text))) => vec![Some(text), None],
+                    (true, _) => vec![None, None],
+                    (false, Some(TypedValue::Int(n))) => vec![Some(n.to_string())],
+                    (false, Some(TypedValue::Text(text))) => vec![Some(text)],
+                    (false, _) => vec![None],
+                }
+            })
+            .map(|value| match value {
+                Some(value) if value.is_empty() => "\"\"".to_string(),
+                Some(value) => csv_field(&value),
+                None => String::new(),
+            })
+            .collect();
+        lines.push(row.join(","));
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+// Cells of each row, with `None` for an empty cell that was not quoted.
+fn parse_csv(inp: &str) -> Result<Vec<Vec<Option<String>>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut chars = inp.chars().peekable();
+    let mut quoted = false;
+    let mut was_quoted = false;
+    let end_field = |field: &mut String, was_quoted: &mut bool| {
+        let field = std::mem::replace(field, String::new());
+        match std::mem::replace(was_quoted, false) {
+            false if field.is_empty() => None,
+            _ => Some(field),
+        }
+    };
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => {
+                quoted = true;
+                was_quoted = true;
+            }
+            (false, ',') => row.push(end_field(&mut field, &mut was_quoted)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(end_field(&mut field, &mut was_quoted));
+                rows.push(std::mem::replace(&mut row, vec![]));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted CSV field".to_string());
+    }
+    if !field.is_empty() || was_quoted || !row.is_empty() {
+        row.push(end_field(&mut field, &mut was_quoted));
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+// The measure a `<field>_value` or `<field>_unit` column belongs to, if the
+// schema has that measure and the header has both of its columns.
+fn measure_of<'a>(column: &'a str, header: &[String], schema: &Schema) -> Option<&'a str> {
+    let name = column
+        .strip_suffix("_value")
+        .or(column.strip_suffix("_unit"))?;
+    let has_column = |suffix: &str| {
+        header
+            .iter()
+            .any(|other| *other == name.to_string() + suffix)
+    };
+    match schema.field(name).map(|rule| &rule.field_type) {
+        Some(FieldType::Measure(_)) if has_column("_value") && has_column("_unit") => Some(name),
+        _ => None,
+    }
+}
+
+// Empty cells are missing fields and `""` is an empty value. The
+// `<field>_value` and `<field>_unit` columns of a measure are joined back into
+// one field.
+fn import_csv(inp: &str, schema: &Schema) -> Result<String, String> {
+    let rows = parse_csv(inp)?;
+    let header: Vec<String> = match rows.first() {
+        Some(header) => header
+            .iter()
+            .map(|column| column.clone().unwrap_or_default())
+            .collect(),
+        None => return Ok(String::new()),
+    };
+    let names: Vec<&str> = header
+        .iter()
+        .map(|column| measure_of(column, &header, schema).unwrap_or(column))
+        .collect();
+    let mut passports = vec![];
+    for (i, row) in rows.iter().enumerate().skip(1) {
+        if row.len() != header.len() {
+            return Err(format!(
+                "Row {} has {} fields, expected {}",
+                i + 1,
+                row.len(),
+                header.len()
+            ));
+        }
+        let mut fields: Vec<(String, String)> = vec![];
+        for (name, value) in names.iter().zip(row) {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            match fields.iter_mut().find(|(key, _)| key == name) {
+                Some((_, joined)) => joined.push_str(value),
+                None => fields.push((name.to_string(), value.clone())),
+            }
+        }
+        passports.push(fields);
+    }
+    write_batch(&passports)
+}
+
+fn load_schema(args: &Args) -> Result<Schema, String> {
+    match args.value("--schema") {
+        Some(path) => Schema::load(path),
+        None => Ok(Schema::default()),
+    }
+}
+
+// `check [--schema schema.toml|schema.json]`: passports with every required
+// field, and passports that are valid, unique rules included.
+fn run_check(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--schema"], &[])?;
+    let schema = load_schema(&args)?;
+    println!(
+        "{} with required fields",
+        count_valid_passport(&inp, |passport| schema.has_required_fields(passport))?
+    );
+    println!("{} valid", count_valid(&inp, &schema)?);
+    Ok(())
+}
+
+// `report [--schema ...] [--json]`: every passport's missing and invalid
+// fields, then how often each reason occurs.
+fn run_report(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--schema"], &["--json"])?;
+    let reports = report(&inp, &load_schema(&args)?)?;
+    if args.flag("--json") {
+        println!("{}", render_report_json(&reports));
+    } else {
+        println!("{}", render_report_text(&reports));
+    }
+    Ok(())
+}
+
+// `export [--format jsonl|csv] [--schema ...]`: the batch as typed records.
+fn run_export(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--format", "--schema"], &[])?;
+    let schema = load_schema(&args)?;
+    match args.value("--format").unwrap_or("jsonl") {
+        "jsonl" => print!("{}", export_jsonl(&inp, &schema)?),
+        "csv" => print!("{}", export_csv(&inp, &schema)?),
+        format => return Err(format!("Unknown format {}, use jsonl or csv", format)),
+    }
+    Ok(())
+}
+
+// `import [--format jsonl|csv] [--schema ...] --input passports.jsonl`:
+// records back to the batch format. CSV measures are found with the schema.
+fn run_import(inp: String, args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &["--format", "--schema"], &[])?;
+    let batch = match args.value("--format").unwrap_or("jsonl") {
+        "jsonl" => import_jsonl(&inp)?,
+        "csv" => import_csv(&inp, &load_schema(&args)?)?,
+        format => return Err(format!("Unknown format {}, use jsonl or csv", format)),
+    };
+    println!("{}", batch);
+    Ok(())
 }
 
-fn validate_hcl(s: &str) -> bool {
-    HCL_REGEX.is_match(s)
+// `schema [--example]`: the built-in schema, to start a custom one from, with
+// example rules across fields and passports if asked.
+fn run_schema(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args, &[], &["--example"])?;
+    print!("{}", DEFAULT_SCHEMA);
+    if args.flag("--example") {
+        print!("{}", EXAMPLE_RULES);
+    }
+    Ok(())
 }
 
-fn validate_pid(s: &str) -> bool {
-    s.len() == 9 && validate_number_between(s, 1..=999_999_999)
+#[allow(dead_code)]
+fn example_schema() -> Schema {
+    Schema::parse(&format!("{}{}", DEFAULT_SCHEMA, EXAMPLE_RULES), false).unwrap()
+}
+
+#[allow(dead_code)]
+fn is_valid_field(name: &str, value: &str) -> bool {
+    Schema::default().field(name).unwrap().check(value).is_ok()
 }
 
 #[test]
 fn test_validate_hgt() {
-    assert_eq!(true, validate_hgt("190cm"));
-    assert_eq!(true, validate_hgt("60in"));
-    assert_eq!(false, validate_hgt("190in"));
-    assert_eq!(false, validate_hgt("190"));
+    assert_eq!(true, is_valid_field("hgt", "190cm"));
+    assert_eq!(true, is_valid_field("hgt", "60in"));
+    assert_eq!(false, is_valid_field("hgt", "190in"));
+    assert_eq!(false, is_valid_field("hgt", "190"));
 }
 
 #[test]
 fn test_validate_hcl() {
-    assert_eq!(true, validate_hcl("#123abc"));
-    assert_eq!(false, validate_hcl("#123abz"));
-    assert_eq!(false, validate_hcl("123abc"));
+    assert_eq!(true, is_valid_field("hcl", "#123abc"));
+    assert_eq!(false, is_valid_field("hcl", "#123abz"));
+    assert_eq!(false, is_valid_field("hcl", "123abc"));
 }
 
 #[test]
 fn test_validate_pid() {
-    assert_eq!(true, validate_pid("000000001"));
-    assert_eq!(false, validate_pid("0123456789"));
-    assert_eq!(false, validate_pid("77110462"));
+    assert_eq!(true, is_valid_field("pid", "000000001"));
+    assert_eq!(false, is_valid_field("pid", "0123456789"));
+    assert_eq!(false, is_valid_field("pid", "77110462"));
+}
+
+#[test]
+fn test_field_reasons() {
+    let schema = Schema::default();
+    assert_eq!(
+        Err("hgt=190in out of 59..76".to_string()),
+        schema
+            .field("hgt")
+            .unwrap()
+            .check("190in")
+            .map_err(|err| err.to_string())
+    );
+    assert_eq!(
+        Err("hgt=190 is not a number in cm, in".to_string()),
+        schema
+            .field("hgt")
+            .unwrap()
+            .check("190")
+            .map_err(|err| err.to_string())
+    );
+    assert_eq!(
+        Err("byr=2003 out of 1920..2002".to_string()),
+        schema
+            .field("byr")
+            .unwrap()
+            .check("2003")
+            .map_err(|err| err.to_string())
+    );
+    assert_eq!(
+        Err("pid=0123456789 does not have 9 digits".to_string()),
+        schema
+            .field("pid")
+            .unwrap()
+            .check("0123456789")
+            .map_err(|err| err.to_string())
+    );
+    assert_eq!(
+        Err("ecl=wat is not one of amb, blu, brn, gry, grn, hzl, oth".to_string()),
+        schema
+            .field("ecl")
+            .unwrap()
+            .check("wat")
+            .map_err(|err| err.to_string())
+    );
+    assert_eq!(Ok(()), schema.field("cid").unwrap().check("anything"));
 }
+
+#[test]
+fn test_count_valid_passport_with_schemas() {
+    let schema = Schema::default();
+    assert_eq!(
+        2,
+        count_valid_passport(TEST_INPUT, |passport| schema.has_required_fields(passport)).unwrap()
+    );
+    assert_eq!(Ok(2), count_valid(TEST_INPUT, &schema));
+    assert!(schema.rules.is_empty());
+
+    let json_schema = Schema::parse(
+        r#"{"fields": {"hcl": {"type": "regex", "pattern": "^#"}, "cid": {"type": "any"}}}"#,
+        true,
+    )
+    .unwrap();
+    assert_eq!(Ok(2), count_valid(TEST_INPUT, &json_schema));
+    assert!(Schema::parse("[fields.byr]\ntype = \"date\"", false).is_err());
+    assert!(Schema::parse("[fields.byr]\ntype = \"int\"\nmin = 1", false).is_err());
+    assert!(Schema::parse("[other]", false).is_err());
+}
+
+#[test]
+fn test_invalid_entries() {
+    let schema = Schema::default();
+    let input = "byr:1937 iyr:2017\n\nhgt:183cm\nbyr1937 ecl:gry";
+    let err = Err("Line 4: expected key:value, found \"byr1937\"".to_string());
+    assert_eq!(
+        err,
+        count_valid_passport(input, |passport| schema.has_required_fields(passport))
+    );
+    assert_eq!(err, report(input, &schema).map(|reports| reports.len()));
+    assert_eq!(
+        Err("Line 1: expected key:value, found \"a:b:c\"".to_string()),
+        report("a:b:c", &schema).map(|reports| reports.len())
+    );
+}
+
+#[test]
+fn test_report() {
+    let input = format!(
+        "{}\n\nhgt:190in byr:2003 iyr:2015\neyr:2025 hcl:#123abc ecl:wat pid:000000001",
+        TEST_INPUT
+    );
+    let reports = report(&input, &Schema::default()).unwrap();
+    assert_eq!(
+        "passport 1 (line 1): valid
+passport 2 (line 4): missing hgt
+passport 3 (line 7): valid
+passport 4 (line 12): missing byr
+passport 5 (line 15): byr=2003 out of 1920..2002; hgt=190in out of 59..76; ecl=wat is not one of amb, blu, brn, gry, grn, hzl, oth
+5 passports: 2 valid, 3 invalid
+byr out of 1920..2002: 1
+ecl is not one of amb, blu, brn, gry, grn, hzl, oth: 1
+hgt out of 59..76: 1
+missing byr: 1
+missing hgt: 1",
+        render_report_text(&reports)
+    );
+    let json = render_report_json(&reports[1..2]);
+    assert_eq!(
+        r#"{"passports":[{"passport":2,"line":4,"valid":false,"missing":["hgt"],"invalid":[],"rules":[]}],"summary":{"total":1,"valid":0,"invalid":1,"reasons":{"missing hgt":1}}}"#,
+        json
+    );
+}
+
+#[test]
+fn test_rules() {
+    let schema = example_schema();
+    let input = "byr:1990 iyr:2015 eyr:2025 hgt:180cm hcl:#123abc ecl:brn pid:000000001
+
+byr:2000 iyr:2020 eyr:2020 hgt:180cm hcl:#123abc ecl:brn pid:000000002
+
+byr:2000 iyr:2019 eyr:2025 hgt:180cm hcl:#123abc ecl:brn pid:000000001
+
+byr:2000 iyr:2020 eyr:2020 hgt:180cm hcl:#123abc ecl:brn pid:000000001";
+    assert_eq!(Ok(4), count_valid(input, &Schema::default()));
+    assert_eq!(Ok(1), count_valid(input, &schema));
+    let reports = report(input, &schema).unwrap();
+    let broken: Vec<Vec<String>> = reports
+        .iter()
+        .map(|report| report.broken.iter().map(|err| err.to_string()).collect())
+        .collect();
+    assert_eq!(
+        vec![
+            vec!["unique-pid (pid): pid=000000001 also in passport 3, 4"],
+            vec![],
+            vec!["unique-pid (pid): pid=000000001 also in passport 1, 4"],
+            vec!["unique-pid (pid): pid=000000001 also in passport 1, 3"],
+        ],
+        broken
+    );
+    assert_eq!(
+        vec!["broke unique-pid"],
+        count_reasons(&reports)
+            .into_iter()
+            .map(|(reason, _)| reason)
+            .collect::<Vec<String>>()
+    );
+
+    let schema = Schema::parse(
+        r#"
+[fields.byr]
+type = "any"
+[fields.iyr]
+type = "any"
+[fields.eyr]
+type = "any"
+[rules.born-before-issue]
+type = "order"
+fields = ["byr", "iyr"]
+strict = true
+[rules.issued-before-expiry]
+type = "order"
+fields = ["iyr", "eyr"]
+"#,
+        false,
+    )
+    .unwrap();
+    let reports = report(
+        "byr:2010 iyr:2010 eyr:2009\n\nbyr:2009 iyr:2010 eyr:2010\n\nbyr:x iyr:2010 eyr:2011",
+        &schema,
+    )
+    .unwrap();
+    assert_eq!(
+        vec![
+            "born-before-issue (byr, iyr): byr=2010 is not before iyr=2010",
+            "issued-before-expiry (iyr, eyr): iyr=2010 is not at most eyr=2009"
+        ],
+        reports[0]
+            .broken
+            .iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<String>>()
+    );
+    assert!(reports[1].is_valid());
+    assert!(reports[2].is_valid());
+    assert_eq!(
+        r#"{"passports":[{"passport":1,"line":1,"valid":false,"missing":[],"invalid":[],"rules":[{"rule":"born-before-issue","fields":["byr","iyr"],"reason":"byr=2010 is not before iyr=2010"},{"rule":"issued-before-expiry","fields":["iyr","eyr"],"reason":"iyr=2010 is not at most eyr=2009"}]}],"summary":{"total":1,"valid":0,"invalid":1,"reasons":{"broke born-before-issue":1,"broke issued-before-expiry":1}}}"#,
+        render_report_json(&reports[..1])
+    );
+    assert_eq!(Ok(0), count_valid("byr:2010 iyr:2010", &schema));
+
+    let field = "[fields.byr]\ntype = \"any\"\n";
+    assert!(Schema::parse(
+        &format!("{}[rules.r]\ntype = \"order\"\nfields = [\"byr\"]", field),
+        false
+    )
+    .is_err());
+    assert!(Schema::parse(
+        &format!("{}[rules.r]\ntype = \"unique\"\nfields = [\"pid\"]", field),
+        false
+    )
+    .is_err());
+    assert!(Schema::parse(
+        &format!("{}[rules.r]\ntype = \"sum\"\nfields = [\"byr\"]", field),
+        false
+    )
+    .is_err());
+}
+
+#[test]
+fn test_export() {
+    let schema = Schema::default();
+    let input = "hgt:190in byr:1937 pid:000000001\ncid:1,2\n\nhgt:tall eyr:20x0";
+    assert_eq!(
+        r#"{"hgt":{"value":190,"unit":"in"},"byr":1937,"pid":"000000001","cid":"1,2"}
+{"hgt":"tall","eyr":"20x0"}
+"#,
+        export_jsonl(input, &schema).unwrap()
+    );
+    assert_eq!(
+        "byr,iyr,eyr,hgt_value,hgt_unit,hcl,ecl,pid,cid
+1937,,,190,in,,,000000001,\"1,2\"
+,,20x0,tall,,,,,
+",
+        export_csv(input, &schema).unwrap()
+    );
+    assert_eq!(
+        Err("Line 2: expected key:value, found \"cid\"".to_string()),
+        export_jsonl("byr:1937\ncid", &schema)
+    );
+    assert!(export_csv("byr:1937\ncid", &schema).is_err());
+}
+
+#[test]
+fn test_export_import_round_trip() {
+    let schema = Schema::default();
+    let input = format!(
+        "{}\n\nhgt:190 extra:\"quoted\" byr:0042\n\nzzz:1 aaa:2",
+        TEST_INPUT
+    );
+    let sorted_batch = |batch: &str| -> Vec<Vec<(String, String)>> {
+        parse_batch(batch)
+            .unwrap()
+            .into_iter()
+            .map(|passport| {
+                let mut fields: Vec<(String, String)> = passport
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                fields.sort();
+                fields
+            })
+            .collect()
+    };
+    let from_jsonl = import_jsonl(&export_jsonl(&input, &schema).unwrap()).unwrap();
+    assert_eq!(sorted_batch(&input), sorted_batch(&from_jsonl));
+    assert_eq!(
+        export_jsonl(&input, &schema),
+        export_jsonl(&from_jsonl, &schema)
+    );
+    let from_csv = import_csv(&export_csv(&input, &schema).unwrap(), &schema).unwrap();
+    assert_eq!(sorted_batch(&input), sorted_batch(&from_csv));
+    assert_eq!(export_csv(&input, &schema), export_csv(&from_csv, &schema));
+
+    assert_eq!(
+        Err("Passport 2: \"hcl\"=\"#1 2\" cannot be written as key:value".to_string()),
+        import_jsonl("{\"byr\": 1937}\n{\"hcl\": \"#1 2\"}")
+    );
+    assert!(import_jsonl("{\"a:b\": 1}").is_err());
+    assert!(import_csv("byr,hcl\n1937,a:b\n", &schema).is_err());
+
+    assert!(import_jsonl("{\"byr\": 19.5}").is_err());
+    assert!(import_jsonl("[1]").is_err());
+    assert!(import_csv("byr,iyr\n1\n", &schema).is_err());
+    assert!(import_csv("byr\n\"1937", &schema).is_err());
+}
+
+#[test]
+fn test_csv_round_trip_keeps_empty_values_and_suffixed_fields() {
+    let schema = Schema::default();
+    let input = "byr:1937 hcl: hgt:\n\niyr:2017 cid:";
+    let csv = export_csv(input, &schema).unwrap();
+    assert_eq!(
+        "byr,iyr,eyr,hgt_value,hgt_unit,hcl,ecl,pid,cid
+1937,,,\"\",,\"\",,,
+,2017,,,,,,,\"\"
+",
+        csv
+    );
+    assert_eq!(
+        "byr:1937 hgt: hcl:\n\niyr:2017 cid:",
+        import_csv(&csv, &schema).unwrap()
+    );
+
+    let input = "pid:1 cid_value:5 cid_unit:cm\n\nhgt:190cm iyr_value:x";
+    let csv = export_csv(input, &schema).unwrap();
+    assert_eq!(
+        "byr,iyr,eyr,hgt_value,hgt_unit,hcl,ecl,pid,cid,cid_unit,cid_value,iyr_value
+,,,,,,,1,,cm,5,
+,,,190,cm,,,,,,,x
+",
+        csv
+    );
+    assert_eq!(
+        "pid:1 cid_unit:cm cid_value:5\n\nhgt:190cm iyr_value:x",
+        import_csv(&csv, &schema).unwrap()
+    );
+    // Without both columns a suffix is just part of the name.
+    assert_eq!(
+        "hgt_value:190",
+        import_csv("hgt_value\n190\n", &schema).unwrap()
+    );
+}
+
+#[allow(dead_code)]
+const TEST_INPUT: &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in";
@@ -1,24 +1,22 @@
-use std::str::Chars;
+use crate::args::Args;
+use crate::days::Tool;
+use std::collections::HashMap;
 
 pub fn part1(inp: String) {
-    let max_seat_id = inp
-        .lines()
-        .map(|l| {
-            let boarding_pass = decode_boarding_pass(l);
-            boarding_pass.seat_id
-        })
+    let max_seat_id = decode_boarding_passes(&inp)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .iter()
+        .map(|boarding_pass| boarding_pass.seat_id)
         .max()
         .unwrap();
     println!("{}", max_seat_id);
 }
 
 pub fn part2(inp: String) {
-    let mut seat_ids: Vec<i32> = inp
-        .lines()
-        .map(|l| {
-            let boarding_pass = decode_boarding_pass(l);
-            boarding_pass.seat_id
-        })
+    let mut seat_ids: Vec<i32> = decode_boarding_passes(&inp)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .iter()
+        .map(|boarding_pass| boarding_pass.seat_id)
         .collect::<Vec<i32>>();
     seat_ids.sort();
     for i in 1..seat_ids.len() {
@@ -29,6 +27,8 @@ pub fn part2(inp: String) {
     }
 }
 
+pub static TOOLS: &[(&str, Tool)] = &[("encode", Tool::Standalone(run_encode))];
+
 #[derive(std::cmp::PartialEq, Debug)]
 struct BoardingPass {
     row: i32,
@@ -36,65 +36,372 @@ struct BoardingPass {
     seat_id: i32,
 }
 
-fn decode_boarding_pass(encoded_boarding_pass: &str) -> BoardingPass {
-    let (encoded_row, encoded_column) = encoded_boarding_pass.split_at(7);
-    let row = decode_chars((0, 127), encoded_row.chars());
-    let column = decode_chars((0, 7), encoded_column.chars());
-    BoardingPass {
-        row: row,
-        column: column,
-        seat_id: row * 8 + column,
+// A plane of 2^row_bits rows and 2^column_bits columns. Each letter pair is
+// (lower half, upper half) of the remaining rows or columns.
+struct Plane {
+    row_bits: u32,
+    column_bits: u32,
+    row_letters: (char, char),
+    column_letters: (char, char),
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Plane::new(7, 3, ('F', 'B'), ('L', 'R')).unwrap()
+    }
+}
+
+impl Plane {
+    // Each letter must stand for one half of one axis, and a seat id must fit
+    // in an i32.
+    fn new(
+        row_bits: u32,
+        column_bits: u32,
+        row_letters: (char, char),
+        column_letters: (char, char),
+    ) -> Result<Plane, String> {
+        for (lower, upper) in &[row_letters, column_letters] {
+            if lower == upper {
+                return Err(format!("{} cannot mean both halves", lower));
+            }
+        }
+        let row_alphabet = [row_letters.0, row_letters.1];
+        if let Some(c) = [column_letters.0, column_letters.1]
+            .iter()
+            .find(|c| row_alphabet.contains(c))
+        {
+            return Err(format!("{} is both a row and a column letter", c));
+        }
+        if row_bits + column_bits > 30 {
+            return Err(format!(
+                "{} row bits and {} column bits is more than 30",
+                row_bits, column_bits
+            ));
+        }
+        Ok(Plane {
+            row_bits: row_bits,
+            column_bits: column_bits,
+            row_letters: row_letters,
+            column_letters: column_letters,
+        })
+    }
+
+    fn rows(&self) -> i32 {
+        1 << self.row_bits
+    }
+
+    fn columns(&self) -> i32 {
+        1 << self.column_bits
+    }
+
+    // The pass read as one binary number: the row bits, then the column bits.
+    fn decode(&self, encoded_boarding_pass: &str) -> Result<BoardingPass, String> {
+        let bits = self.row_bits + self.column_bits;
+        let length = encoded_boarding_pass.chars().count();
+        if length != bits as usize {
+            return Err(format!(
+                "{:?} has {} letters, expected {}",
+                encoded_boarding_pass, length, bits
+            ));
+        }
+        let mut seat = 0;
+        for (i, c) in encoded_boarding_pass.chars().enumerate() {
+            let (lower, upper) = if i < self.row_bits as usize {
+                self.row_letters
+            } else {
+                self.column_letters
+            };
+            let bit = match c {
+                c if c == lower => 0,
+                c if c == upper => 1,
+                c => {
+                    return Err(format!(
+                        "{:?} has {:?} at position {}, expected {} or {}",
+                        encoded_boarding_pass,
+                        c,
+                        i + 1,
+                        lower,
+                        upper
+                    ))
+                }
+            };
+            seat = seat << 1 | bit;
+        }
+        let row = seat >> self.column_bits;
+        let column = seat & (self.columns() - 1);
+        Ok(BoardingPass {
+            row: row,
+            column: column,
+            seat_id: row * self.columns() + column,
+        })
+    }
+
+    fn encode(&self, row: i32, column: i32) -> Result<String, String> {
+        if !(0..self.rows()).contains(&row) || !(0..self.columns()).contains(&column) {
+            return Err(format!(
+                "Seat {},{} is outside {}x{}",
+                row,
+                column,
+                self.rows(),
+                self.columns()
+            ));
+        }
+        Ok(encode_bits(row, self.row_bits, self.row_letters)
+            + &encode_bits(column, self.column_bits, self.column_letters))
     }
 }
 
-fn decode_chars(mut range: (i32, i32), encoded_chars: Chars<'_>) -> i32 {
-    let initial_range = range;
-    for c in encoded_chars {
-        let mid = ((range.0 + range.1) as f32) / 2.0;
-        match c {
-            'B' | 'R' => range = (mid.ceil() as i32, range.1),
-            'F' | 'L' => range = (range.0, mid.floor() as i32),
-            _ => panic!("[decode_chars] Unknown char {} {:?}", c, initial_range),
+fn decode_boarding_pass(encoded_boarding_pass: &str) -> Result<BoardingPass, String> {
+    Plane::default().decode(encoded_boarding_pass)
+}
+
+// Every pass in the input, one per line. A pass seen twice is an error, as
+// two people cannot have the same seat.
+fn decode_boarding_passes(inp: &str) -> Result<Vec<BoardingPass>, String> {
+    let mut lines_by_seat: HashMap<i32, usize> = HashMap::new();
+    let mut boarding_passes = vec![];
+    for (i, line) in inp.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let boarding_pass =
+            decode_boarding_pass(line).map_err(|err| format!("Line {}: {}", i + 1, err))?;
+        if let Some(first_line) = lines_by_seat.insert(boarding_pass.seat_id, i + 1) {
+            return Err(format!(
+                "Line {}: {:?} is a duplicate of line {}",
+                i + 1,
+                line,
+                first_line
+            ));
         }
+        boarding_passes.push(boarding_pass);
     }
-    if range.0 != range.1 {
-        panic!("[decode_chars] Not equal: {:?} {:?}", range, initial_range);
+    Ok(boarding_passes)
+}
+
+#[allow(dead_code)]
+fn encode_boarding_pass(row: i32, column: i32) -> Result<String, String> {
+    Plane::default().encode(row, column)
+}
+
+// The most significant bit first, as the upper letter when it is set.
+fn encode_bits(n: i32, bits: u32, letters: (char, char)) -> String {
+    (0..bits)
+        .rev()
+        .map(|bit| {
+            if n >> bit & 1 == 1 {
+                letters.1
+            } else {
+                letters.0
+            }
+        })
+        .collect()
+}
+
+// Two letters such as `FB`, for the lower then the upper half.
+fn letter_pair(args: &Args, name: &str, default: (char, char)) -> Result<(char, char), String> {
+    let letters = match args.value(name) {
+        Some(letters) => letters,
+        None => return Ok(default),
+    };
+    match letters.chars().collect::<Vec<char>>()[..] {
+        [lower, upper] => Ok((lower, upper)),
+        _ => Err(format!("{} needs two letters, got {:?}", name, letters)),
     }
-    return range.0;
+}
+
+// `encode [--row-bits 7] [--column-bits 3] [--row-letters FB]
+// [--column-letters LR] <row> <column>`: the boarding pass for a seat.
+fn run_encode(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        args,
+        &[
+            "--row-bits",
+            "--column-bits",
+            "--row-letters",
+            "--column-letters",
+        ],
+        &[],
+    )?;
+    let plane = Plane::new(
+        args.parsed("--row-bits", 7)?,
+        args.parsed("--column-bits", 3)?,
+        letter_pair(&args, "--row-letters", ('F', 'B'))?,
+        letter_pair(&args, "--column-letters", ('L', 'R'))?,
+    )?;
+    let (row, column) = match &args.positional[..] {
+        [row, column] => (
+            row.parse().map_err(|_| format!("Invalid row: {}", row))?,
+            column
+                .parse()
+                .map_err(|_| format!("Invalid column: {}", column))?,
+        ),
+        _ => return Err("encode needs a row and a column".to_string()),
+    };
+    println!("{}", plane.encode(row, column)?);
+    Ok(())
 }
 
 #[test]
 fn test_decode_boarding_pass() {
     assert_eq!(
         decode_boarding_pass("FBFBBFFRLR"),
-        BoardingPass {
+        Ok(BoardingPass {
             row: 44,
             column: 5,
             seat_id: 357
-        }
+        })
     );
     assert_eq!(
         decode_boarding_pass("BFFFBBFRRR"),
-        BoardingPass {
+        Ok(BoardingPass {
             row: 70,
             column: 7,
             seat_id: 567
-        }
+        })
     );
     assert_eq!(
         decode_boarding_pass("FFFBBBFRRR"),
-        BoardingPass {
+        Ok(BoardingPass {
             row: 14,
             column: 7,
             seat_id: 119
-        }
+        })
     );
     assert_eq!(
         decode_boarding_pass("BBFFBBFRLL"),
-        BoardingPass {
+        Ok(BoardingPass {
             row: 102,
             column: 4,
             seat_id: 820
+        })
+    );
+}
+
+#[test]
+fn test_encode_boarding_pass() {
+    assert_eq!(Ok("FBFBBFFRLR".to_string()), encode_boarding_pass(44, 5));
+    assert_eq!(Ok("BBFFBBFRLL".to_string()), encode_boarding_pass(102, 4));
+    for row in 0..128 {
+        for column in 0..8 {
+            let encoded = encode_boarding_pass(row, column).unwrap();
+            let decoded = decode_boarding_pass(&encoded).unwrap();
+            assert_eq!((row, column), (decoded.row, decoded.column));
+            assert_eq!(row * 8 + column, decoded.seat_id);
         }
+    }
+    assert_eq!(
+        Err("Seat 128,0 is outside 128x8".to_string()),
+        encode_boarding_pass(128, 0)
+    );
+    assert!(encode_boarding_pass(0, 8).is_err());
+    assert!(encode_boarding_pass(-1, 0).is_err());
+}
+
+#[test]
+fn test_plane_round_trip() {
+    let mut rng = crate::rng::Rng::new(5);
+    let letters = ['F', 'B', 'L', 'R', 'U', 'D', 'X', 'Y'];
+    for _ in 0..200 {
+        let mut pick = || letters[rng.range(0, letters.len() as i64) as usize];
+        let (low, high) = (pick(), pick());
+        let (left, right) = (pick(), pick());
+        let plane = match Plane::new(
+            rng.range(1, 11) as u32,
+            rng.range(0, 6) as u32,
+            (low, high),
+            (left, right),
+        ) {
+            Ok(plane) => plane,
+            Err(_) => continue,
+        };
+        let row = rng.range(0, plane.rows() as i64) as i32;
+        let column = rng.range(0, plane.columns() as i64) as i32;
+        let encoded = plane.encode(row, column).unwrap();
+        assert_eq!(
+            (plane.row_bits + plane.column_bits) as usize,
+            encoded.chars().count()
+        );
+        assert_eq!(
+            Ok(BoardingPass {
+                row: row,
+                column: column,
+                seat_id: row * plane.columns() + column
+            }),
+            plane.decode(&encoded)
+        );
+    }
+}
+
+#[test]
+fn test_plane_new_errors() {
+    assert!(Plane::new(7, 3, ('F', 'B'), ('L', 'R')).is_ok());
+    assert_eq!(
+        Err("F cannot mean both halves".to_string()),
+        Plane::new(7, 3, ('F', 'F'), ('L', 'R')).map(|_| ())
+    );
+    assert!(Plane::new(7, 3, ('F', 'B'), ('R', 'R')).is_err());
+    assert_eq!(
+        Err("B is both a row and a column letter".to_string()),
+        Plane::new(7, 3, ('F', 'B'), ('L', 'B')).map(|_| ())
+    );
+    assert!(Plane::new(7, 3, ('F', 'B'), ('F', 'R')).is_err());
+    assert_eq!(
+        Err("25 row bits and 6 column bits is more than 30".to_string()),
+        Plane::new(25, 6, ('F', 'B'), ('L', 'R')).map(|_| ())
+    );
+    assert!(Plane::new(20, 10, ('F', 'B'), ('L', 'R')).is_ok());
+}
+
+#[test]
+fn test_decode_errors() {
+    assert_eq!(
+        Err("\"FBFBBFFRL\" has 9 letters, expected 10".to_string()),
+        decode_boarding_pass("FBFBBFFRL")
+    );
+    assert_eq!(
+        Err("\"FBFBBFFRLRR\" has 11 letters, expected 10".to_string()),
+        decode_boarding_pass("FBFBBFFRLRR")
+    );
+    assert_eq!(
+        Err("\"FBFBBFRRLR\" has 'R' at position 7, expected F or B".to_string()),
+        decode_boarding_pass("FBFBBFRRLR")
+    );
+    assert_eq!(
+        Err("\"FBFBBFFRLé\" has 'é' at position 10, expected L or R".to_string()),
+        decode_boarding_pass("FBFBBFFRLé")
+    );
+    assert_eq!(
+        3,
+        decode_boarding_passes("FBFBBFFRLR\r\nBFFFBBFRRR\n\nFFFBBBFRRR\n")
+            .unwrap()
+            .len()
+    );
+    assert_eq!(
+        Err("Line 3: \"FBFBBFFRLR\" is a duplicate of line 1".to_string()),
+        decode_boarding_passes("FBFBBFFRLR\nBFFFBBFRRR\nFBFBBFFRLR")
+    );
+    assert_eq!(
+        Err("Line 2: \"BFFFBBFRR\" has 9 letters, expected 10".to_string()),
+        decode_boarding_passes("FBFBBFFRLR\nBFFFBBFRR")
+    );
+}
+
+// Passes are checked in line order, so whichever problem comes first is the
+// one reported.
+#[test]
+fn test_decode_invalid_and_duplicate_passes() {
+    assert_eq!(
+        Err("Line 3: \"FBFBBFFRLR\" is a duplicate of line 1".to_string()),
+        decode_boarding_passes("FBFBBFFRLR\nBFFFBBFRRR\nFBFBBFFRLR\nFBFBBFFXLR")
+    );
+    assert_eq!(
+        Err("Line 2: \"FBFBBFFXLR\" has 'X' at position 8, expected L or R".to_string()),
+        decode_boarding_passes("FBFBBFFRLR\nFBFBBFFXLR\nFBFBBFFRLR")
+    );
+    assert_eq!(
+        Err("Line 4: \"BFFFBBFRRR\" is a duplicate of line 2".to_string()),
+        decode_boarding_passes("FBFBBFFRLR\r\nBFFFBBFRRR\r\n\r\nBFFFBBFRRR\r\nFBF\r\n")
     );
 }
@@ -1,3 +1,4 @@
+use crate::records;
 use std::collections::HashSet;
 
 pub fn part1(inp: String) {
@@ -12,10 +13,12 @@ fn count_chars_from_groups_with_combiner(
     inp: String,
     combiner: fn(&HashSet<char>, &HashSet<char>) -> HashSet<char>,
 ) -> usize {
-    inp.split("\n\n")
-        .map(|group_str| {
-            group_str
-                .split("\n")
+    records::sections(&inp)
+        .iter()
+        .map(|group| {
+            group
+                .lines
+                .iter()
                 .map(|entry| entry.chars().collect::<HashSet<char>>())
                 .fold(None, |acc: Option<HashSet<char>>, s| match acc {
                     Some(acc_s) => Some(combiner(&acc_s, &s)),
@@ -73,6 +73,11 @@ fn compute_uniq_containers_count_containing(
         );
         let old_count = containers.len();
         containers = containers.union(&new_containers).cloned().collect();
+        debug!(
+            "expanded containers from {} to {} bags",
+            old_count,
+            containers.len()
+        );
         if old_count == containers.len() {
             return old_count - 1;
         }
@@ -83,6 +88,7 @@ fn compute_contained_bags_count_of(
     rules: HashMap<String, Vec<Content>>,
     container_name: String,
 ) -> i32 {
+    debug!("expanding {}", container_name);
     match rules.get(&container_name) {
         None => 0,
         Some(contents) => contents
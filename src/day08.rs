@@ -2,8 +2,7 @@ use std::collections::HashSet;
 
 pub fn part1(inp: String) {
     let ops = parse_operations(inp);
-    let (halted, acc) = execute_operations(ops);
-    assert_eq!(false, halted);
+    let (_, acc) = execute_operations(ops);
     println!("{:?}", acc);
 }
 
@@ -36,6 +35,7 @@ fn execute_operations(ops: Vec<(String, i32)>) -> (bool, i32) {
         }
         already_executed_ops.insert(op_counter);
         let (op, param) = &ops[op_counter as usize];
+        trace!("#{} {} {:+} (acc = {})", op_counter, op, param, acc);
         match op.as_str() {
             "acc" => {
                 acc = acc + param;
@@ -60,9 +60,24 @@ fn replace_corrupted_op_and_execute_operations(ops: Vec<(String, i32)>) -> i32 {
         let mut new_ops = ops.clone();
         new_ops[i] = (replacement.to_string(), *param);
         let (halted, acc) = execute_operations(new_ops);
+        debug!(
+            "replaced #{} {} with {}: halted = {}",
+            i, op, replacement, halted
+        );
         if halted {
             return acc;
         }
     }
     return -1;
 }
+
+// The known answer for the checked-in input, which part 1 no longer asserts so
+// it can run on other inputs.
+#[test]
+#[ignore]
+fn test_part1_on_input() {
+    let inp = std::fs::read_to_string("inputs/08").unwrap();
+    let (halted, acc) = execute_operations(parse_operations(inp));
+    assert_eq!(false, halted);
+    assert_eq!(1594, acc);
+}
@@ -48,7 +48,7 @@ fn search_sum_with_index(numbers: Vec<i64>, sum: i64, interval: Range<usize>) ->
 fn find_contiguous_list_that_sums_up_to(numbers: Vec<i64>, sum: i64) -> Option<Vec<i64>> {
     let mut sum_list_size = 2;
     while sum_list_size < numbers.len() {
-        // println!("{}", sum_list_size);
+        trace!("trying contiguous lists of size {}", sum_list_size);
         match find_sum_list_with_sum_equal_to(numbers.clone(), sum_list_size, sum) {
             Some(sum_list) => return Some(sum_list),
             None => {}
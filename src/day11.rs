@@ -14,6 +14,14 @@ pub fn part2(inp: String) {
     );
 }
 
+pub fn visualize1(inp: String, show: &mut dyn FnMut(String)) {
+    visualize_seating(inp, build_neighbour_map1, apply_rules1, show);
+}
+
+pub fn visualize2(inp: String, show: &mut dyn FnMut(String)) {
+    visualize_seating(inp, build_neighbour_map2, apply_rules2, show);
+}
+
 type NeighbourMap = HashMap<(usize, usize), Vec<(usize, usize)>>;
 type Seats = Vec<Vec<char>>;
 
@@ -54,6 +62,33 @@ fn simulate_seatings_until_no_change_and_count_occupied_seats(
     }
 }
 
+fn visualize_seating(
+    seats: String,
+    neighbour_map_builder: fn(&Seats) -> NeighbourMap,
+    rules_applier: fn(seat: char, adjacent_seats: Vec<char>) -> char,
+    show: &mut dyn FnMut(String),
+) {
+    let mut seats: Seats = seats.lines().map(|l| l.chars().collect()).collect();
+    let neighbour_map = neighbour_map_builder(&seats);
+    show(render_seats(&seats));
+    loop {
+        let new_seats = simulate_seating(seats.clone(), &neighbour_map, rules_applier);
+        if new_seats == seats {
+            return;
+        }
+        seats = new_seats;
+        show(render_seats(&seats));
+    }
+}
+
+fn render_seats(seats: &Seats) -> String {
+    seats
+        .iter()
+        .map(|seat_line| seat_line.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 fn simulate_seating(
     seats: Seats,
     neighbour_map: &NeighbourMap,
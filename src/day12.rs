@@ -19,6 +19,51 @@ pub fn part2(inp: String) {
     println!("{}", ship.pos.re.abs() + ship.pos.im.abs())
 }
 
+pub fn visualize1(inp: String, show: &mut dyn FnMut(String)) {
+    let mut ship = Ship1 {
+        pos: Complex::new(0, 0),
+        dir: Complex::new(1, 0),
+    };
+    show(render_ship("start", ship.pos, "direction", ship.dir));
+    for command in inp.lines() {
+        ship = apply_commands1(ship, vec![command.to_string()]);
+        show(render_ship(command, ship.pos, "direction", ship.dir));
+    }
+}
+
+pub fn visualize2(inp: String, show: &mut dyn FnMut(String)) {
+    let mut ship = Ship2 {
+        pos: Complex::new(0, 0),
+        waypoint: Complex::new(10, 1),
+    };
+    show(render_ship("start", ship.pos, "waypoint", ship.waypoint));
+    for command in inp.lines() {
+        ship = apply_commands2(ship, vec![command.to_string()]);
+        show(render_ship(command, ship.pos, "waypoint", ship.waypoint));
+    }
+}
+
+fn render_ship(command: &str, pos: Complex<i32>, label: &str, vector: Complex<i32>) -> String {
+    format!(
+        "{}\nship: {}\n{}: {}\ndistance: {}",
+        command,
+        render_complex(pos),
+        label,
+        render_complex(vector),
+        pos.re.abs() + pos.im.abs()
+    )
+}
+
+fn render_complex(c: Complex<i32>) -> String {
+    format!(
+        "{} {}, {} {}",
+        if c.re < 0 { "west" } else { "east" },
+        c.re.abs(),
+        if c.im < 0 { "south" } else { "north" },
+        c.im.abs()
+    )
+}
+
 #[derive(Debug, std::cmp::PartialEq)]
 struct Ship1 {
     pos: Complex<i32>,
@@ -8,6 +8,24 @@ pub fn part2(inp: String) {
     println!("{}", compute_numbers_until(inp, 30000000))
 }
 
+pub static PART1_VARIANTS: &[(&str, fn(String) -> String)] = &[
+    ("hash-map", |inp| {
+        compute_numbers_until(inp, 2020).to_string()
+    }),
+    ("array", |inp| {
+        compute_numbers_until_with_array(inp, 2020).to_string()
+    }),
+];
+
+pub static PART2_VARIANTS: &[(&str, fn(String) -> String)] = &[
+    ("hash-map", |inp| {
+        compute_numbers_until(inp, 30000000).to_string()
+    }),
+    ("array", |inp| {
+        compute_numbers_until_with_array(inp, 30000000).to_string()
+    }),
+];
+
 fn compute_numbers_until(starting_numbers_str: String, max_count: usize) -> i32 {
     let numbers: Vec<i32> = starting_numbers_str
         .split(",")
@@ -33,6 +51,28 @@ fn compute_numbers_until(starting_numbers_str: String, max_count: usize) -> i32
     current_number
 }
 
+fn compute_numbers_until_with_array(starting_numbers_str: String, max_count: usize) -> i32 {
+    let numbers: Vec<usize> = starting_numbers_str
+        .trim()
+        .split(",")
+        .map(|x| x.parse().unwrap())
+        .collect();
+    let mut last_seen: Vec<u32> = vec![0; max_count.max(*numbers.iter().max().unwrap()) + 1];
+    for (i, &number) in numbers.iter().take(numbers.len() - 1).enumerate() {
+        last_seen[number] = (i + 1) as u32;
+    }
+    let mut current_number = *numbers.last().unwrap();
+    for turn in numbers.len()..max_count {
+        let next_number = match last_seen[current_number] {
+            0 => 0,
+            seen => turn - seen as usize,
+        };
+        last_seen[current_number] = turn as u32;
+        current_number = next_number;
+    }
+    current_number as i32
+}
+
 #[test]
 fn test_compute_numbers_until() {
     assert_eq!(436, compute_numbers_until("0,3,6".to_string(), 2020));
@@ -43,3 +83,25 @@ fn test_compute_numbers_until() {
     assert_eq!(438, compute_numbers_until("3,2,1".to_string(), 2020));
     assert_eq!(1836, compute_numbers_until("3,1,2".to_string(), 2020));
 }
+
+#[test]
+fn test_variants_agree_on_examples() {
+    for example in &[
+        "0,3,6", "1,3,2", "2,1,3", "1,2,3", "2,3,1", "3,2,1", "3,1,2",
+    ] {
+        let expected = PART1_VARIANTS[0].1(example.to_string());
+        for (name, variant) in PART1_VARIANTS {
+            assert_eq!(expected, variant(example.to_string()), "variant {}", name);
+        }
+    }
+}
+
+#[test]
+fn test_algorithms_agree_beyond_2020_turns() {
+    for example in &["0,3,6", "3,1,2"] {
+        assert_eq!(
+            compute_numbers_until(example.to_string(), 100_000),
+            compute_numbers_until_with_array(example.to_string(), 100_000)
+        );
+    }
+}
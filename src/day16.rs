@@ -1,3 +1,4 @@
+use crate::records;
 use regex::Regex;
 use std::clone::Clone;
 use std::collections::{HashMap, HashSet};
@@ -73,20 +74,25 @@ fn compute_ticket_scanning_error(inp: String) -> i32 {
 }
 
 fn parse_input(inp: String) -> (Vec<Field>, Validator, Ticket, Vec<Ticket>) {
-    match inp.split("\n\n").collect::<Vec<&str>>()[..] {
-        [rules_str, ticket_str, nearby_tickets_str] => {
-            let fields: Vec<Field> = rules_str.lines().map(parse_field).collect();
-            let validator = rules_to_validator(fields.clone());
-            let ticket: Ticket = ticket_str.lines().skip(1).map(parse_ticket).next().unwrap();
-            let nearby_tickets: Vec<Ticket> = nearby_tickets_str
-                .lines()
-                .skip(1)
-                .map(parse_ticket)
-                .collect();
-            return (fields, validator, ticket, nearby_tickets);
-        }
-        _ => panic!("Invalid input"),
-    }
+    let sections = records::expect_sections(&inp, 3).unwrap_or_else(|err| panic!("{}", err));
+    let fields: Vec<Field> = sections[0].lines.iter().cloned().map(parse_field).collect();
+    let validator = rules_to_validator(fields.clone());
+    let ticket: Ticket = sections[1]
+        .lines
+        .iter()
+        .cloned()
+        .skip(1)
+        .map(parse_ticket)
+        .next()
+        .unwrap();
+    let nearby_tickets: Vec<Ticket> = sections[2]
+        .lines
+        .iter()
+        .cloned()
+        .skip(1)
+        .map(parse_ticket)
+        .collect();
+    return (fields, validator, ticket, nearby_tickets);
 }
 
 fn parse_field(field_str: &str) -> Field {
@@ -159,6 +165,10 @@ fn build_field_indices(fields: Vec<Field>, tickets: Vec<Ticket>) -> HashMap<Stri
         let potential_indices_for_name = potential_indices.get_mut(&name).unwrap();
         assert!(potential_indices_for_name.len() == 1);
         let index = *potential_indices_for_name.iter().next().unwrap();
+        debug!(
+            "field {} is at index {}, eliminating it elsewhere",
+            name, index
+        );
         indices.insert(name.clone(), index);
         names_left.remove(&name);
         for name_left in &names_left {
@@ -11,8 +11,17 @@ pub fn part2(inp: String) {
     println!("{}", parse_input_and_simulate_cycles2(inp))
 }
 
+pub fn visualize1(inp: String, show: &mut dyn FnMut(String)) {
+    visualize_cycles(6, parse_input(inp, Cube::init), show);
+}
+
+pub fn visualize2(inp: String, show: &mut dyn FnMut(String)) {
+    visualize_cycles(6, parse_input(inp, HyperCube::init), show);
+}
+
 trait CubeLike: Eq + PartialEq + Hash + Clone + Copy {
     fn compute_neighbours(&self) -> HashSet<Self>;
+    fn project(&self) -> (i32, i32, Vec<(&'static str, i32)>);
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Copy)]
@@ -45,6 +54,9 @@ impl CubeLike for Cube {
         }
         neighbours
     }
+    fn project(&self) -> (i32, i32, Vec<(&'static str, i32)>) {
+        (self.x, self.y, vec![("z", self.z)])
+    }
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Copy)]
@@ -90,6 +102,9 @@ impl CubeLike for HyperCube {
         }
         neighbours
     }
+    fn project(&self) -> (i32, i32, Vec<(&'static str, i32)>) {
+        (self.x, self.y, vec![("z", self.z), ("w", self.w)])
+    }
 }
 
 fn parse_input_and_simulate_cycles1(inp: String) -> i32 {
@@ -122,6 +137,56 @@ fn simulate_cycles<T: CubeLike>(mut count: i32, mut active_cubes: HashSet<T>) ->
     active_cubes.len() as i32
 }
 
+fn visualize_cycles<T: CubeLike>(
+    count: i32,
+    mut active_cubes: HashSet<T>,
+    show: &mut dyn FnMut(String),
+) {
+    show(render_cubes(0, &active_cubes));
+    for cycle in 1..=count {
+        active_cubes = simulate_cycle(active_cubes.clone());
+        show(render_cubes(cycle, &active_cubes));
+    }
+}
+
+fn render_cubes<T: CubeLike>(cycle: i32, active_cubes: &HashSet<T>) -> String {
+    let projections: HashSet<(i32, i32, Vec<(&str, i32)>)> =
+        active_cubes.iter().map(|cube| cube.project()).collect();
+    let min_x = projections.iter().map(|(x, _, _)| *x).min().unwrap_or(0);
+    let max_x = projections.iter().map(|(x, _, _)| *x).max().unwrap_or(0);
+    let min_y = projections.iter().map(|(_, y, _)| *y).min().unwrap_or(0);
+    let max_y = projections.iter().map(|(_, y, _)| *y).max().unwrap_or(0);
+    let mut layers: Vec<Vec<(&str, i32)>> = projections
+        .iter()
+        .map(|(_, _, layer)| layer.clone())
+        .collect::<HashSet<Vec<(&str, i32)>>>()
+        .into_iter()
+        .collect();
+    layers.sort_by_key(|layer| layer.iter().map(|(_, n)| *n).collect::<Vec<i32>>());
+    let mut res = format!("After {} cycles: {} active", cycle, active_cubes.len());
+    for layer in layers {
+        let label = layer
+            .iter()
+            .map(|(name, n)| format!("{}={}", name, n))
+            .collect::<Vec<String>>()
+            .join(", ");
+        res = format!("{}\n\n{}", res, label);
+        for y in min_y..=max_y {
+            let line: String = (min_x..=max_x)
+                .map(|x| {
+                    if projections.contains(&(x, y, layer.clone())) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            res = format!("{}\n{}", res, line);
+        }
+    }
+    res
+}
+
 fn simulate_cycle<T: CubeLike>(active_cubes: HashSet<T>) -> HashSet<T> {
     let mut new_active_cubes = HashSet::new();
     let mut non_active_cubes_with_active_neighbour_count: HashMap<T, usize> = HashMap::new();
@@ -1,3 +1,4 @@
+use crate::records;
 use regex::Regex;
 use std::clone::Clone;
 use std::collections::HashMap;
@@ -45,13 +46,11 @@ fn parse_input_and_count_matching_messages(inp: String) -> usize {
 }
 
 fn parse_input(inp: String) -> (Rules, Messages) {
-    match inp.split("\n\n").collect::<Vec<&str>>()[..] {
-        [rules_str, messages_str] => (
-            rules_str.lines().map(parse_rule).collect(),
-            messages_str.lines().map(|s| s.to_string()).collect(),
-        ),
-        _ => panic!("Unexpected input"),
-    }
+    let sections = records::expect_sections(&inp, 2).unwrap_or_else(|err| panic!("{}", err));
+    (
+        sections[0].lines.iter().cloned().map(parse_rule).collect(),
+        sections[1].lines.iter().map(|s| s.to_string()).collect(),
+    )
 }
 
 fn parse_rule(rule_str: &str) -> (i32, Rule) {
@@ -1,3 +1,4 @@
+use crate::records;
 use itertools::Itertools;
 use na::{Column, DMatrix, DVector, Row, Transpose};
 use regex::Regex;
@@ -6,7 +7,6 @@ use std::collections::{HashMap, HashSet};
 
 pub fn part1(inp: String) {
     let value = parse_tiles_and_compute_border_product_of_matching(inp);
-    assert_eq!(15405893262491, value);
     println!("{}", value);
 }
 
@@ -202,9 +202,10 @@ fn parse_tiles_and_reconstruct_image(inp: String) -> Grid {
 
 fn parse_tiles(inp: String) -> Tiles {
     let tile_id_regex: Regex = Regex::new(r"^Tile (\d+):$").unwrap();
-    inp.split("\n\n")
-        .map(|tile_str| {
-            let mut tile_lines = tile_str.lines();
+    records::sections(&inp)
+        .iter()
+        .map(|tile| {
+            let mut tile_lines = tile.lines.iter().cloned();
             let tile_id_line = tile_lines.next().unwrap();
             let tile_id = tile_id_regex
                 .captures(tile_id_line)
@@ -334,8 +335,8 @@ fn reconstruct_image(tiles: Tiles, matching: Matching) -> Grid {
     let mut next_index = (0, 0);
     let mut delta_index: i8 = 1;
     loop {
-        // println!("{:?}", tile_grid);
-        // println!("{:?}", next_index);
+        trace!("{:?}", tile_grid);
+        trace!("{:?}", next_index);
         tile_grid[next_index] = current_tile;
         unused_tiles.remove(&current_tile.0);
         if unused_tiles.len() == 0 {
@@ -669,3 +670,15 @@ const TEST_IMAGE: &str = r#".#.#..#.##...#.##..#####
 .#.###..##..##..####.##.
 ...###...##...#...#..###
 "#;
+
+// The known answer for the checked-in input, which part 1 no longer asserts so
+// it can run on other inputs.
+#[test]
+#[ignore]
+fn test_part1_on_input() {
+    let inp = std::fs::read_to_string("inputs/20").unwrap();
+    assert_eq!(
+        15405893262491,
+        parse_tiles_and_compute_border_product_of_matching(inp)
+    );
+}
@@ -1,3 +1,4 @@
+use crate::records;
 use itertools::Itertools;
 use regex::Regex;
 use std::clone::Clone;
@@ -40,7 +41,12 @@ fn parse_input_and_compute_canonical_dangerous_ingredients(inp: String) -> Strin
 }
 
 fn parse_input(inp: String) -> Vec<Food> {
-    inp.lines().map(parse_food).collect()
+    records::expect_sections(&inp, 1).unwrap_or_else(|err| panic!("{}", err))[0]
+        .lines
+        .iter()
+        .cloned()
+        .map(parse_food)
+        .collect()
 }
 
 fn parse_food(food_str: &str) -> Food {
@@ -108,6 +114,10 @@ fn match_allergens_to_ingredients(foods: Vec<Food>) -> HashMap<Ingredient, Aller
             .find(|(_, ingredients)| ingredients.len() == 1)
             .unwrap();
         let ingredient = ingredients.iter().next().unwrap();
+        debug!(
+            "{} contains {}, eliminating it elsewhere",
+            ingredient, allergen
+        );
         ingredients_with_allergen.insert(ingredient.clone(), allergen.clone());
         ingredients_by_allergen.remove(allergen);
         for ingredients in ingredients_by_allergen.values_mut() {
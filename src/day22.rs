@@ -1,3 +1,4 @@
+use crate::records;
 use itertools::Itertools;
 use std::collections::{HashSet, VecDeque};
 
@@ -15,6 +16,19 @@ pub fn part2(inp: String) {
     );
 }
 
+pub fn visualize2(inp: String, show: &mut dyn FnMut(String)) {
+    let (deck1, deck2) = parse_player_decks(inp);
+    let (winner, deck) =
+        do_simulate_recursive_combat(deck1, deck2, 1, &mut |game, deck1, deck2| {
+            show(render_round(game, deck1, deck2))
+        });
+    show(format!(
+        "Player {} wins with score {}",
+        winner,
+        compute_score(deck)
+    ));
+}
+
 type Card = u8;
 type Deck = VecDeque<Card>;
 
@@ -28,10 +42,12 @@ fn parse_player_decks_and_simulate_combat_and_compute_score(
 }
 
 fn parse_player_decks(inp: String) -> (Deck, Deck) {
-    inp.split("\n\n")
-        .map(|deck_str| {
-            deck_str
-                .lines()
+    let sections = records::expect_sections(&inp, 2).unwrap_or_else(|err| panic!("{}", err));
+    sections
+        .iter()
+        .map(|deck| {
+            deck.lines
+                .iter()
                 .skip(1)
                 .map(|card_str| card_str.parse::<Card>().unwrap())
                 .collect()
@@ -62,11 +78,16 @@ fn simulate_combat(mut deck1: Deck, mut deck2: Deck) -> Deck {
 }
 
 fn simulate_recursive_combat(deck1: Deck, deck2: Deck) -> Deck {
-    let (_, deck) = do_simulate_recursive_combat(deck1, deck2);
+    let (_, deck) = do_simulate_recursive_combat(deck1, deck2, 1, &mut |_, _, _| {});
     deck
 }
 
-fn do_simulate_recursive_combat(mut deck1: Deck, mut deck2: Deck) -> (u8, Deck) {
+fn do_simulate_recursive_combat(
+    mut deck1: Deck,
+    mut deck2: Deck,
+    game: usize,
+    on_round: &mut dyn FnMut(usize, &Deck, &Deck),
+) -> (u8, Deck) {
     let mut previous_configurations: HashSet<(Deck, Deck)> = HashSet::new();
     loop {
         let configuration = (deck1.clone(), deck2.clone());
@@ -78,13 +99,20 @@ fn do_simulate_recursive_combat(mut deck1: Deck, mut deck2: Deck) -> (u8, Deck)
             return (1, deck1);
         }
         previous_configurations.insert(configuration);
+        on_round(game, &deck1, &deck2);
         let card1 = deck1.pop_front().unwrap();
         let card2 = deck2.pop_front().unwrap();
+        trace!("round: {} vs {}", card1, card2);
         if card1 as usize <= deck1.len() && card2 as usize <= deck2.len() {
-            match do_simulate_recursive_combat(
+            debug!("sub-game with {} and {} cards", card1, card2);
+            let sub_game_result = do_simulate_recursive_combat(
                 deck1.iter().take(card1 as usize).cloned().collect(),
                 deck2.iter().take(card2 as usize).cloned().collect(),
-            ) {
+                game + 1,
+                on_round,
+            );
+            debug!("sub-game won by player {}", sub_game_result.0);
+            match sub_game_result {
                 (1, _) => {
                     deck1.push_back(card1);
                     deck1.push_back(card2);
@@ -107,6 +135,15 @@ fn do_simulate_recursive_combat(mut deck1: Deck, mut deck2: Deck) -> (u8, Deck)
     }
 }
 
+fn render_round(game: usize, deck1: &Deck, deck2: &Deck) -> String {
+    format!(
+        "Game depth {}\nPlayer 1: {}\nPlayer 2: {}",
+        game,
+        deck1.iter().join(", "),
+        deck2.iter().join(", ")
+    )
+}
+
 fn compute_score(deck: Deck) -> i32 {
     let deck_size = deck.len();
     deck.iter()
@@ -126,6 +163,14 @@ fn test_parse_player_decks_and_simulate_combat_and_compute_score() {
     )
 }
 
+#[test]
+fn test_parse_player_decks_with_crlf() {
+    assert_eq!(
+        parse_player_decks(TEST_INPUT.to_string()),
+        parse_player_decks(TEST_INPUT.replace("\n", "\r\n") + "\r\n\r\n")
+    )
+}
+
 #[allow(dead_code)]
 const TEST_INPUT: &str = r#"Player 1:
 9
@@ -62,9 +62,6 @@ const MAX_CUP: Cup = 9;
 
 pub fn part1(inp: String) {
     let res = parse_input_and_simulate_moves_and_compute_result(inp, 100);
-    assert_ne!("95486237", res);
-    assert_ne!("95237648", res);
-    assert_eq!("47382659", res);
     println!("{}", res);
 }
 
@@ -72,10 +69,33 @@ pub fn part2(inp: String) {
     let res = parse_input_and_extend_cups_and_simulate_moves_and_compute_product_of_cups_with_star(
         inp, 10_000_000,
     );
-    assert_eq!(42271866720, res);
     println!("{}", res);
 }
 
+pub fn visualize1(inp: String, show: &mut dyn FnMut(String)) {
+    let mut cups = parse_input(inp);
+    show(render_cups(0, &mut cups));
+    for move_nr in 1..=100 {
+        let current_cup = cups.first.unwrap();
+        simulate_moves(1, &mut cups, MAX_CUP);
+        // simulate_moves always starts from the first cup, so rotate the circle
+        // to make the next current cup the first one.
+        cups.first = Some(cups.peek_next_of(current_cup));
+        show(render_cups(move_nr, &mut cups));
+    }
+}
+
+fn render_cups(move_nr: usize, cups: &mut Cups) -> String {
+    let current_cup = cups.first.unwrap();
+    let mut res = format!("After move {}: ({})", move_nr, current_cup);
+    let mut cup = cups.peek_next_of(current_cup);
+    while cup != current_cup {
+        res = format!("{} {}", res, cup);
+        cup = cups.peek_next_of(cup);
+    }
+    res
+}
+
 fn parse_input_and_simulate_moves_and_compute_result(inp: String, move_nr: usize) -> String {
     let mut cups = parse_input(inp);
     simulate_moves(move_nr, &mut cups, MAX_CUP);
@@ -164,3 +184,22 @@ fn test_parse_input_and_extend_cups_and_simulate_moves_and_compute_product_of_cu
     );
     assert_eq!(149245887792, res);
 }
+
+// The known answers for the checked-in input, which the parts no longer assert
+// so they can run on other inputs. 95486237 and 95237648 were earlier wrong
+// answers.
+#[test]
+#[ignore]
+fn test_parts_on_input() {
+    let inp = std::fs::read_to_string("inputs/23").unwrap();
+    let res = parse_input_and_simulate_moves_and_compute_result(inp.clone(), 100);
+    assert_ne!("95486237", res);
+    assert_ne!("95237648", res);
+    assert_eq!("47382659", res);
+    assert_eq!(
+        42271866720,
+        parse_input_and_extend_cups_and_simulate_moves_and_compute_product_of_cups_with_star(
+            inp, 10_000_000,
+        )
+    );
+}
@@ -8,6 +8,15 @@ pub fn part2(inp: String) {
     println!("{}", parse_input_and_flip_tiles(inp));
 }
 
+pub fn visualize2(inp: String, show: &mut dyn FnMut(String)) {
+    let mut black_tiles = flip_initial_tiles(parse_input(inp));
+    show(render_tiles(0, &black_tiles));
+    for day in 1..=100 {
+        black_tiles = do_flip_tiles(black_tiles);
+        show(render_tiles(day, &black_tiles));
+    }
+}
+
 type Tiles = Vec<Tile>;
 type Tile = String;
 type Coords = (i32, i32, i32);
@@ -107,6 +116,43 @@ fn do_flip_tiles(black_tiles: HashSet<Coords>) -> HashSet<Coords> {
     new_black_tiles
 }
 
+// Rows are the z axis; east/west neighbours are two columns apart and the
+// diagonal ones sit half way between them on the next row.
+fn render_tiles(day: usize, black_tiles: &HashSet<Coords>) -> String {
+    let positions: HashSet<(i32, i32)> = black_tiles
+        .iter()
+        .map(|(x, _, z)| (*z, 2 * x + z))
+        .collect();
+    let min_row = positions.iter().map(|(row, _)| *row).min().unwrap_or(0);
+    let max_row = positions.iter().map(|(row, _)| *row).max().unwrap_or(0);
+    let min_column = positions
+        .iter()
+        .map(|(_, column)| *column)
+        .min()
+        .unwrap_or(0);
+    let max_column = positions
+        .iter()
+        .map(|(_, column)| *column)
+        .max()
+        .unwrap_or(0);
+    let mut res = format!("Day {}: {}", day, black_tiles.len());
+    for row in min_row..=max_row {
+        let line: String = (min_column..=max_column)
+            .map(|column| {
+                if (row - column) % 2 != 0 {
+                    ' '
+                } else if positions.contains(&(row, column)) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        res = format!("{}\n{}", res, line);
+    }
+    res
+}
+
 fn compute_neighbours((x, y, z): Coords) -> HashSet<Coords> {
     let mut neighbours = HashSet::new();
     for (dx, dy, dz) in &NEIGHBOUR_COORDS {
@@ -86,3 +86,131 @@ pub fn get_day(day: u32) -> (DayFn, DayFn) {
         }
     };
 }
+
+pub type VariantFn = fn(String) -> String;
+pub type Variants = &'static [(&'static str, VariantFn)];
+
+pub fn get_variants(day: u32) -> (Variants, Variants) {
+    return match day {
+        1 => (day01::PART1_VARIANTS, day01::PART2_VARIANTS),
+        15 => (day15::PART1_VARIANTS, day15::PART2_VARIANTS),
+        _ => (&[], &[]),
+    };
+}
+
+pub type PolicyFn = fn(String, &str) -> Result<String, String>;
+
+pub fn get_policy_solver(day: u32) -> Option<PolicyFn> {
+    return match day {
+        2 => Some(day02::solve_with_policy),
+        _ => None,
+    };
+}
+
+#[allow(dead_code)]
+fn assert_variants_agree(day: u32, variants: Variants, input: &str) {
+    let answers: Vec<(&str, String)> = variants
+        .iter()
+        .map(|(name, variant)| (*name, variant(input.to_string())))
+        .collect();
+    for (name, answer) in answers.iter().skip(1) {
+        assert_eq!(&answers[0].1, answer, "day {} variant {}", day, name);
+    }
+}
+
+// Day 15's part 2 variants always play 30 million turns, so day15 checks the
+// same algorithms on fewer turns instead.
+#[test]
+fn test_variants_agree_on_examples() {
+    let examples: &[(u32, u32, &str)] = &[
+        (1, 1, "1721\n979\n366\n299\n675\n1456"),
+        (1, 1, "-5\n2025\n3\n4"),
+        (1, 2, "1721\n979\n366\n299\n675\n1456"),
+        (1, 2, "2020\n0\n0\n7"),
+        (15, 1, "0,3,6"),
+        (15, 1, "3,1,2"),
+    ];
+    for (day, part, input) in examples {
+        let (part1_variants, part2_variants) = get_variants(*day);
+        let variants = if *part == 1 {
+            part1_variants
+        } else {
+            part2_variants
+        };
+        assert!(variants.len() > 1, "day {} part {}", day, part);
+        assert_variants_agree(*day, variants, input);
+    }
+}
+
+// Slow in debug builds (day 15 part 2 plays 30 million turns per variant), so
+// run it with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn test_variants_agree_on_inputs() {
+    for day in 1..=25 {
+        let (part1_variants, part2_variants) = get_variants(day);
+        if part1_variants.is_empty() && part2_variants.is_empty() {
+            continue;
+        }
+        let filename = std::path::Path::new("inputs").join(format!("{:02}", day));
+        let input = std::fs::read_to_string(&filename)
+            .unwrap_or_else(|err| panic!("{}: {}", filename.display(), err));
+        for variants in &[part1_variants, part2_variants] {
+            assert_variants_agree(day, variants, &input);
+        }
+    }
+}
+
+pub type VisualizeFn = fn(String, &mut dyn FnMut(String));
+
+pub fn get_visualizations(day: u32) -> (Option<VisualizeFn>, Option<VisualizeFn>) {
+    return match day {
+        11 => (Some(day11::visualize1), Some(day11::visualize2)),
+        12 => (Some(day12::visualize1), Some(day12::visualize2)),
+        17 => (Some(day17::visualize1), Some(day17::visualize2)),
+        22 => (None, Some(day22::visualize2)),
+        23 => (Some(day23::visualize1), None),
+        24 => (None, Some(day24::visualize2)),
+        _ => (None, None),
+    };
+}
+
+pub type ToolFn = fn(String, &[String]) -> Result<(), String>;
+
+pub enum Tool {
+    // Gets the day's input, or the file or stdin given with `--input <file|->`.
+    WithInput(ToolFn),
+    // Reads no input up front; any `--input` is left in its arguments.
+    Standalone(fn(&[String]) -> Result<(), String>),
+}
+
+pub fn get_tools(day: u32) -> &'static [(&'static str, Tool)] {
+    return match day {
+        1 => day01::TOOLS,
+        2 => day02::TOOLS,
+        3 => day03::TOOLS,
+        4 => day04::TOOLS,
+        5 => day05::TOOLS,
+        _ => &[],
+    };
+}
+
+pub fn get_tool(day: u32, name: &str) -> Option<&'static Tool> {
+    get_tools(day)
+        .iter()
+        .find(|(tool_name, _)| *tool_name == name)
+        .map(|(_, tool)| tool)
+}
+
+// These must not need inputs/{day} or read the whole input before they start.
+#[test]
+fn test_standalone_tools() {
+    for (day, name) in &[(2, "generate"), (2, "stream"), (4, "schema"), (5, "encode")] {
+        assert!(
+            matches!(get_tool(*day, name), Some(Tool::Standalone(_))),
+            "day {} tool {}",
+            day,
+            name
+        );
+    }
+}
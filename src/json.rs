@@ -0,0 +1,354 @@
+pub fn string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+pub fn object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", string(key), value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+pub fn array(values: &[String]) -> String {
+    format!("[{}]", values.join(","))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    // Numbers without a fraction or exponent that fit, kept exact.
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    // Keys in document order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_object()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Vec<(String, Value)>> {
+        match self {
+            Value::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(s: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: s.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(parser.error(&format!("unexpected {:?} after the value", c))),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("Invalid JSON at character {}: {}", self.pos, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            c => Err(self.error(&format!("expected {:?}, found {:?}", expected, c))),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('n') => self.keyword("null", Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            c => Err(self.error(&format!("unexpected {:?}", c))),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(fields)),
+                c => return Err(self.error(&format!("expected ',' or '}}', found {:?}", c))),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                c => return Err(self.error(&format!("expected ',' or ']', found {:?}", c))),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => s.push(self.unicode_escape()?),
+                    c => return Err(self.error(&format!("invalid escape {:?}", c))),
+                },
+                Some(c) if (c as u32) >= 0x20 => s.push(c),
+                c => return Err(self.error(&format!("unexpected {:?} in string", c))),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| self.error("invalid \\u escape"))
+    }
+
+    // A character outside the Basic Multilingual Plane is a high surrogate
+    // directly followed by a low one; either on its own is an error.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            self.expect('\\')?;
+            self.expect('u')?;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("high surrogate without a low surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else if (0xdc00..0xe000).contains(&high) {
+            return Err(self.error("low surrogate without a high surrogate"));
+        } else {
+            high
+        };
+        std::char::from_u32(code).ok_or(self.error("invalid \\u escape"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || "+-.eE".contains(c) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let number: String = self.chars[start..self.pos].iter().collect();
+        let mut digits = number.trim_start_matches('-').chars();
+        if digits.next() == Some('0') && digits.next().map_or(false, |c| c.is_ascii_digit()) {
+            return Err(self.error(&format!("leading zero in {}", number)));
+        }
+        if !number.contains(|c| ".eE".contains(c)) {
+            if let Ok(n) = number.parse() {
+                return Ok(Value::Integer(n));
+            }
+        }
+        number
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| self.error(&format!("invalid number {}", number)))
+    }
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(r#""a\"b\\c\nd""#, string("a\"b\\c\nd"));
+    assert_eq!(r#""\u0001""#, string("\u{1}"));
+}
+
+#[test]
+fn test_object() {
+    assert_eq!(
+        r#"{"day":1,"answer":"514579"}"#,
+        object(&[("day", "1".to_string()), ("answer", string("514579"))])
+    );
+}
+
+#[test]
+fn test_array() {
+    assert_eq!("[]", array(&[]));
+    assert_eq!(r#"[1,"a"]"#, array(&["1".to_string(), string("a")]));
+}
+
+#[test]
+fn test_parse() {
+    let value = parse(r#" {"a": [1, -2.5, true, null], "b": {"c": "x\"\né😀"}} "#).unwrap();
+    assert_eq!(
+        Some(&Value::Array(vec![
+            Value::Integer(1),
+            Value::Number(-2.5),
+            Value::Bool(true),
+            Value::Null
+        ])),
+        value.get("a")
+    );
+    assert_eq!(
+        Some("x\"\né😀"),
+        value
+            .get("b")
+            .and_then(|b| b.get("c"))
+            .and_then(|c| c.as_str())
+    );
+    assert_eq!(
+        Some(1),
+        value.get("a").unwrap().as_array().unwrap()[0].as_i64()
+    );
+    assert_eq!(
+        Ok(Value::String("é😀".to_string())),
+        parse(r#""\u00e9\ud83d\ude00""#)
+    );
+    assert_eq!(Ok(Value::Object(vec![])), parse("{}"));
+    assert_eq!(
+        Some(9007199254740993),
+        parse("9007199254740993").unwrap().as_i64()
+    );
+    assert_eq!(Ok(Value::Number(1.0)), parse("1.0"));
+    assert_eq!(None, parse("1.0").unwrap().as_i64());
+    assert_eq!(Ok(Value::Number(1e19)), parse("10000000000000000000"));
+    assert!(parse("{\"a\": 1,}").is_err());
+    assert!(parse("[1] 2").is_err());
+    assert!(parse("\"abc").is_err());
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        Err("Invalid JSON at character 13: high surrogate without a low surrogate".to_string()),
+        parse(r#""\ud83d\u0041""#)
+    );
+    assert!(parse(r#""\ud83d\ud83d""#).is_err());
+    assert!(parse(r#""\ud83d""#).is_err());
+    assert_eq!(
+        Err("Invalid JSON at character 7: low surrogate without a high surrogate".to_string()),
+        parse(r#""\ude00""#)
+    );
+    assert_eq!(
+        Err("Invalid JSON at character 2: leading zero in 01".to_string()),
+        parse("01")
+    );
+    assert!(parse("-007").is_err());
+    assert!(parse("[00]").is_err());
+    assert_eq!(Ok(Value::Integer(0)), parse("0"));
+    assert_eq!(Ok(Value::Integer(0)), parse("-0"));
+    assert_eq!(Ok(Value::Number(0.5)), parse("0.5"));
+    assert_eq!(Ok(Value::Number(0.0)), parse("0e1"));
+}
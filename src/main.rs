@@ -1,5 +1,7 @@
 use std::env;
 use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 #[macro_use]
@@ -7,7 +9,36 @@ extern crate lazy_static;
 extern crate nalgebra as na;
 extern crate regex;
 
+#[macro_use]
+mod trace;
+mod args;
+mod cache;
 mod days;
+mod json;
+mod records;
+mod rng;
+mod runner;
+mod serve;
+mod toml;
+mod visualize;
+
+use cache::Cache;
+use runner::SolveError;
+
+const DEFAULT_CACHE_DIR: &str = ".cache";
+
+struct Options {
+    day_nums: Vec<u32>,
+    variant: Option<String>,
+    policy: Option<String>,
+    bench: Option<u32>,
+    verbosity: u8,
+    part: Option<u32>,
+    input: Option<String>,
+    cache_dir: Option<PathBuf>,
+    refresh_cache: bool,
+    clear_cache: bool,
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -15,23 +46,240 @@ fn main() {
         panic!("You need to provide a day");
     }
 
-    let day = args[1].clone().trim().to_string();
-    let day_num: u32 = match day.parse() {
-        Ok(num) => num,
-        Err(_) => {
-            println!("Invalid day number: {}", day);
+    if args[1] == "visualize" {
+        match visualize::parse_options(&args[2..]) {
+            Ok(options) => {
+                let input = read_input(options.day_num, None);
+                visualize::run(options, input);
+            }
+            Err(err) => println!("{}", err),
+        }
+        return;
+    }
+
+    if args[1] == "serve" {
+        match serve::parse_options(&args[2..]) {
+            Ok(options) => serve::run(options),
+            Err(err) => println!("{}", err),
+        }
+        return;
+    }
+
+    if let Some(tool_name) = args.get(2) {
+        if let Ok(day_num) = args[1].trim().parse() {
+            if let Some(tool) = days::get_tool(day_num, tool_name) {
+                run_tool(day_num, tool, &args[3..]);
+                return;
+            }
+        }
+    }
+
+    let options = match parse_options(&args[1..]) {
+        Ok(options) => options,
+        Err(err) => {
+            println!("{}", err);
             return;
         }
     };
+    trace::set_level(options.verbosity);
+    let cache = match &options.cache_dir {
+        Some(dir) => match Cache::open(dir.clone(), options.refresh_cache, options.clear_cache) {
+            Ok(cache) => Some(cache),
+            Err(err) => {
+                println!("Error while opening cache {}: {}", dir.display(), err);
+                return;
+            }
+        },
+        None => None,
+    };
+    for day_num in options.day_nums.iter() {
+        run(&options, *day_num, cache.as_ref());
+    }
+}
 
-    let cwd = env::current_dir().unwrap();
-    let filename = cwd.join("inputs").join(format!("{:02}", day_num));
-    println!("Reading {}", filename.display());
-    let input = fs::read_to_string(filename).expect("Error while reading");
+fn run(options: &Options, day_num: u32, cache: Option<&Cache>) {
+    let input = read_input(day_num, options.input.as_deref());
+    let run_part1 = options.part != Some(2);
+    let run_part2 = options.part != Some(1);
+
+    let variants = days::get_variants(day_num);
+    if let Some(iterations) = options.bench {
+        if run_part1 {
+            bench_variants("Part 1", variants.0, &input, iterations);
+        }
+        if run_part2 {
+            bench_variants("Part 2", variants.1, &input, iterations);
+        }
+        return;
+    }
+    if let Some(variant) = &options.variant {
+        if run_part1 {
+            run_variant("Part 1", variants.0, variant, input.clone());
+        }
+        if run_part2 {
+            run_variant("Part 2", variants.1, variant, input.clone());
+        }
+        return;
+    }
+    if let Some(policy) = &options.policy {
+        run_policy(day_num, policy, input);
+        return;
+    }
+
+    if let Some(cache) = cache {
+        if run_part1 {
+            run_cached_day("Part 1", day_num, 1, input.clone(), cache);
+        }
+        if run_part2 {
+            run_cached_day("Part 2", day_num, 2, input.clone(), cache);
+        }
+        return;
+    }
 
     let to_run = days::get_day(day_num);
-    run_day("Part 1", to_run.0, input.clone());
-    run_day("Part 2", to_run.1, input.clone());
+    if run_part1 {
+        run_day("Part 1", to_run.0, input.clone());
+    }
+    if run_part2 {
+        run_day("Part 2", to_run.1, input.clone());
+    }
+}
+
+fn read_input(day_num: u32, path: Option<&str>) -> String {
+    println!("Reading {}", input_name(day_num, path));
+    load_input(day_num, path)
+}
+
+fn input_name(day_num: u32, path: Option<&str>) -> String {
+    match path {
+        Some("-") => "stdin".to_string(),
+        Some(path) => path.to_string(),
+        None => default_input_path(day_num).display().to_string(),
+    }
+}
+
+fn default_input_path(day_num: u32) -> PathBuf {
+    let cwd = env::current_dir().unwrap();
+    cwd.join("inputs").join(format!("{:02}", day_num))
+}
+
+fn load_input(day_num: u32, path: Option<&str>) -> String {
+    match path {
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .expect("Error while reading");
+            input
+        }
+        Some(path) => fs::read_to_string(path).expect("Error while reading"),
+        None => fs::read_to_string(default_input_path(day_num)).expect("Error while reading"),
+    }
+}
+
+// `<day> <tool> [args]` runs one of the day's extra tools. Tools write only
+// their own output, so it can be piped; `--input <file|->` replaces the input.
+// Standalone tools read nothing here and handle any `--input` themselves.
+fn run_tool(day_num: u32, tool: &days::Tool, args: &[String]) {
+    let tool = match tool {
+        days::Tool::WithInput(tool) => tool,
+        days::Tool::Standalone(tool) => return exit_on_error(tool(args)),
+    };
+    let mut path = None;
+    let mut tool_args = vec![];
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match (arg.as_str(), rest.clone().next()) {
+            ("--input", Some(value)) => {
+                path = Some(value.clone());
+                rest.next();
+            }
+            _ => tool_args.push(arg.clone()),
+        }
+    }
+    let input = load_input(day_num, path.as_deref());
+    exit_on_error(tool(input, &tool_args));
+}
+
+fn exit_on_error(result: Result<(), String>) {
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let day = args[0].trim();
+    let day_nums: Vec<u32> = match day {
+        "all" => (1..=25).collect(),
+        _ => vec![day
+            .parse()
+            .map_err(|_| format!("Invalid day number: {}", day))?],
+    };
+    let mut options = Options {
+        day_nums: day_nums,
+        variant: None,
+        policy: None,
+        bench: None,
+        verbosity: 0,
+        part: None,
+        input: None,
+        cache_dir: None,
+        refresh_cache: false,
+        clear_cache: false,
+    };
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--variant" => {
+                let name = rest.next().ok_or("--variant needs a name")?;
+                options.variant = Some(name.clone());
+            }
+            "--policy" => {
+                let name = rest.next().ok_or("--policy needs a name")?;
+                options.policy = Some(name.clone());
+            }
+            "--bench" => {
+                let iterations = rest.next().ok_or("--bench needs an iteration count")?;
+                options.bench = Some(
+                    iterations
+                        .parse()
+                        .map_err(|_| format!("Invalid iteration count: {}", iterations))?,
+                );
+            }
+            "--part" => {
+                let part = rest.next().ok_or("--part needs 1 or 2")?;
+                options.part = Some(match part.as_str() {
+                    "1" => 1,
+                    "2" => 2,
+                    _ => return Err(format!("Invalid part: {}", part)),
+                });
+            }
+            "--input" => {
+                let path = rest.next().ok_or("--input needs a file name or -")?;
+                options.input = Some(path.clone());
+            }
+            "--cache" => {
+                options.cache_dir = options.cache_dir.or(Some(PathBuf::from(DEFAULT_CACHE_DIR)))
+            }
+            "--cache-dir" => {
+                let dir = rest.next().ok_or("--cache-dir needs a directory")?;
+                options.cache_dir = Some(PathBuf::from(dir));
+            }
+            "--refresh-cache" => {
+                options.refresh_cache = true;
+                options.cache_dir = options.cache_dir.or(Some(PathBuf::from(DEFAULT_CACHE_DIR)));
+            }
+            "--clear-cache" => {
+                options.clear_cache = true;
+                options.cache_dir = options.cache_dir.or(Some(PathBuf::from(DEFAULT_CACHE_DIR)));
+            }
+            "-v" => options.verbosity = trace::DEBUG,
+            "-vv" => options.verbosity = trace::TRACE,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(options)
 }
 
 fn run_day(name: &str, part_fn: days::DayFn, input: String) {
@@ -46,6 +294,79 @@ fn run_day(name: &str, part_fn: days::DayFn, input: String) {
     println!("Took {}", fmt_dur(part1_dur));
 }
 
+fn run_cached_day(name: &str, day_num: u32, part: u32, input: String, cache: &Cache) {
+    if let Some(solution) = cache.get(day_num, part, &input) {
+        println!("Running {} (cached)", name);
+        println!("{}", solution.answer);
+        println!("Took {} when it was cached", solution.took);
+        return;
+    }
+    println!("Running {}", name);
+    match runner::run_in_child(day_num, part, input.clone(), None) {
+        Ok(solution) => {
+            println!("{}", solution.answer);
+            println!("Took {}", solution.took);
+            if let Err(err) = cache.put(day_num, part, &input, &solution) {
+                println!("Error while caching: {}", err);
+            }
+        }
+        Err(SolveError::Failed(err)) => println!("Failed: {}", err),
+        Err(SolveError::Timeout) => println!("Timed out"),
+    }
+}
+
+fn run_variant(name: &str, variants: days::Variants, variant: &str, input: String) {
+    println!("Running {} ({})", name, variant);
+    match variants
+        .iter()
+        .find(|(variant_name, _)| *variant_name == variant)
+    {
+        Some((_, variant_fn)) => {
+            let start = Instant::now();
+            println!("{}", variant_fn(input));
+            println!("Took {}", fmt_dur(start.elapsed()));
+        }
+        None => println!(
+            "Unknown variant, available: {:?}",
+            variants
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<&str>>()
+        ),
+    }
+}
+
+fn run_policy(day_num: u32, policy: &str, input: String) {
+    println!("Running policy {}", policy);
+    match days::get_policy_solver(day_num) {
+        Some(policy_fn) => {
+            let start = Instant::now();
+            match policy_fn(input, policy) {
+                Ok(answer) => println!("{}", answer),
+                Err(err) => println!("Failed: {}", err),
+            }
+            println!("Took {}", fmt_dur(start.elapsed()));
+        }
+        None => println!("Day {} has no policies", day_num),
+    }
+}
+
+fn bench_variants(name: &str, variants: days::Variants, input: &String, iterations: u32) {
+    println!("Benchmarking {} ({} iterations)", name, iterations);
+    if variants.is_empty() {
+        println!("No variants registered");
+    }
+    for (variant_name, variant_fn) in variants {
+        let start = Instant::now();
+        let mut answer = String::new();
+        for _ in 0..iterations {
+            answer = variant_fn(input.clone());
+        }
+        let mean = start.elapsed() / iterations.max(1);
+        println!("{}: {} (mean {})", variant_name, answer, fmt_dur(mean));
+    }
+}
+
 fn fmt_time(ms: f64) -> String {
     if ms <= 1.0 {
         let micro_sec = ms * 1000.0;
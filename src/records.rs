@@ -0,0 +1,89 @@
+// Puzzle inputs made of sections separated by blank lines, such as passports,
+// answer groups or tiles. Lines are split on `\n` with any `\r` and trailing
+// spaces removed, lines of only whitespace count as blank, and blank lines
+// before, between and after sections are dropped.
+pub struct Section<'a> {
+    // 1-based line number of the section's first line.
+    pub line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+pub fn sections(inp: &str) -> Vec<Section<'_>> {
+    let mut sections = vec![];
+    let mut current: Option<Section> = None;
+    for (i, line) in inp.split('\n').enumerate() {
+        let line = line.trim_end();
+        if line.trim_start().is_empty() {
+            sections.extend(current.take());
+            continue;
+        }
+        current
+            .get_or_insert(Section {
+                line: i + 1,
+                lines: vec![],
+            })
+            .lines
+            .push(line);
+    }
+    sections.extend(current);
+    sections
+}
+
+// The sections of an input that must have exactly `count` of them.
+pub fn expect_sections(inp: &str, count: usize) -> Result<Vec<Section<'_>>, String> {
+    let sections = sections(inp);
+    match sections.len() {
+        n if n == count => Ok(sections),
+        n => Err(format!(
+            "Expected {} section{} separated by blank lines, found {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            n
+        )),
+    }
+}
+
+#[test]
+fn test_sections() {
+    let inp = "\r\na b\r\nc\r\n\r\n  \n\nd  \n\n\n";
+    let found = sections(inp);
+    assert_eq!(2, found.len());
+    assert_eq!(2, found[0].line);
+    assert_eq!(vec!["a b", "c"], found[0].lines);
+    assert_eq!(7, found[1].line);
+    assert_eq!(vec!["d"], found[1].lines);
+
+    assert_eq!(0, sections("\n \n").len());
+    assert!(expect_sections(inp, 2).is_ok());
+    assert_eq!(
+        Some("Expected 3 sections separated by blank lines, found 2".to_string()),
+        expect_sections(inp, 3).err()
+    );
+    assert_eq!(
+        Some("Expected 1 section separated by blank lines, found 2".to_string()),
+        expect_sections(inp, 1).err()
+    );
+}
+
+#[test]
+fn test_sections_line_endings() {
+    let lines = |found: &[Section<'static>]| -> Vec<(usize, Vec<&'static str>)> {
+        found
+            .iter()
+            .map(|section| (section.line, section.lines.clone()))
+            .collect()
+    };
+    let expected = vec![(1, vec!["a", "b"]), (4, vec!["c"])];
+    assert_eq!(expected, lines(&sections("a\nb\n\nc\n")));
+    assert_eq!(expected, lines(&sections("a\r\nb\r\n\r\nc\r\n")));
+    assert_eq!(expected, lines(&sections("a\nb\n\nc")));
+    assert_eq!(expected, lines(&sections("a\r\nb\r\n\r\nc")));
+
+    let spaced = sections("a\n\n\n\nb\r\n\r\n \r\n\r\nc\n\n\n");
+    assert_eq!(
+        vec![(1, vec!["a"]), (5, vec!["b"]), (9, vec!["c"])],
+        lines(&spaced)
+    );
+    assert_eq!(0, sections("").len());
+    assert_eq!(0, sections("\r\n\r\n").len());
+}
@@ -0,0 +1,38 @@
+// A small seedable xorshift64* generator, so results can be reproduced from the
+// seed alone.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed ^ 0x9e37_79b9_7f4a_7c15 | 1,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // A number from the half-open range [start, end).
+    pub fn range(&mut self, start: i64, end: i64) -> i64 {
+        assert!(start < end, "Empty range {}..{}", start, end);
+        start + (self.next_u64() % (end - start) as u64) as i64
+    }
+}
+
+#[test]
+fn test_same_seed_same_numbers() {
+    let mut rng1 = Rng::new(42);
+    let mut rng2 = Rng::new(42);
+    for _ in 0..100 {
+        let n = rng1.range(-5, 5);
+        assert_eq!(n, rng2.range(-5, 5));
+        assert!((-5..5).contains(&n));
+    }
+    assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+}
@@ -0,0 +1,104 @@
+use std::env;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct Solution {
+    pub answer: String,
+    pub took: String,
+}
+
+pub enum SolveError {
+    Timeout,
+    Failed(String),
+}
+
+// Runs one part in a child process of this binary and captures its answer, so a
+// timeout can kill it and a panicking solver does not take the caller down.
+pub fn run_in_child(
+    day: u32,
+    part: u32,
+    input: String,
+    timeout: Option<Duration>,
+) -> Result<Solution, SolveError> {
+    let exe = env::current_exe().map_err(|err| SolveError::Failed(err.to_string()))?;
+    let mut child = Command::new(exe)
+        .args(&[
+            day.to_string(),
+            "--part".to_string(),
+            part.to_string(),
+            "--input".to_string(),
+            "-".to_string(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| SolveError::Failed(err.to_string()))?;
+    let mut stdin = child.stdin.take().unwrap();
+    thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout_reader = thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stdout.read_to_string(&mut output);
+        output
+    });
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr_reader = thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output);
+        output
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if timeout.map_or(false, |timeout| start.elapsed() > timeout) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(SolveError::Timeout);
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(5)),
+            Err(err) => return Err(SolveError::Failed(err.to_string())),
+        }
+    };
+    let output = stdout_reader.join().unwrap_or_default();
+    if !status.success() {
+        let errors = stderr_reader.join().unwrap_or_default();
+        return Err(SolveError::Failed(
+            errors
+                .lines()
+                .find(|line| line.contains("panicked"))
+                .unwrap_or("Solver failed")
+                .to_string(),
+        ));
+    }
+    Ok(parse_run_output(&output))
+}
+
+fn parse_run_output(output: &str) -> Solution {
+    let mut answer = vec![];
+    let mut took = String::new();
+    for line in output.lines() {
+        if line.starts_with("Reading ") || line.starts_with("Running ") {
+            continue;
+        }
+        match line.strip_prefix("Took ") {
+            Some(duration) => took = duration.to_string(),
+            None => answer.push(line),
+        }
+    }
+    Solution {
+        answer: answer.join("\n"),
+        took: took,
+    }
+}
+
+#[test]
+fn test_parse_run_output() {
+    let solution = parse_run_output("Reading stdin\nRunning Part 1\n514579\nTook 12µs\n");
+    assert_eq!("514579", solution.answer);
+    assert_eq!("12µs", solution.took);
+}
@@ -0,0 +1,362 @@
+use crate::json;
+use crate::runner::{self, Solution, SolveError};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct Options {
+    pub port: u16,
+    pub timeout: Duration,
+    pub max_body: usize,
+    pub io_timeout: Duration,
+}
+
+// The request line and headers together, like other servers' default limits.
+const MAX_HEAD: usize = 8 * 1024;
+
+pub fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        port: 8020,
+        timeout: Duration::from_secs(30),
+        max_body: 1024 * 1024,
+        io_timeout: Duration::from_secs(10),
+    };
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let mut value = |name: &str| {
+            rest.next()
+                .ok_or(format!("{} needs a value", name))
+                .map(|value| value.clone())
+        };
+        match arg.as_str() {
+            "--port" => {
+                let port = value("--port")?;
+                options.port = port
+                    .parse()
+                    .map_err(|_| format!("Invalid port: {}", port))?;
+            }
+            "--timeout" => {
+                let seconds = value("--timeout")?;
+                options.timeout = Duration::from_secs_f64(
+                    seconds
+                        .parse()
+                        .ok()
+                        .filter(|seconds: &f64| *seconds > 0.0)
+                        .ok_or(format!("Invalid timeout: {}", seconds))?,
+                );
+            }
+            "--max-body" => {
+                let bytes = value("--max-body")?;
+                options.max_body = bytes
+                    .parse()
+                    .map_err(|_| format!("Invalid body size: {}", bytes))?;
+            }
+            "--io-timeout" => {
+                let seconds = value("--io-timeout")?;
+                options.io_timeout = Duration::from_secs_f64(
+                    seconds
+                        .parse()
+                        .ok()
+                        .filter(|seconds: &f64| *seconds > 0.0)
+                        .ok_or(format!("Invalid I/O timeout: {}", seconds))?,
+                );
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+pub type Solver = fn(u32, u32, String, Duration) -> Result<Solution, SolveError>;
+
+pub fn run(options: Options) {
+    let listener = TcpListener::bind(("127.0.0.1", options.port)).expect("Error while binding");
+    println!("Listening on http://{}", listener.local_addr().unwrap());
+    serve(listener, Arc::new(options), |day, part, input, timeout| {
+        runner::run_in_child(day, part, input, Some(timeout))
+    });
+}
+
+fn serve(listener: TcpListener, options: Arc<Options>, solver: Solver) {
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let options = options.clone();
+        thread::spawn(move || {
+            let response =
+                match set_timeouts(&stream, options.io_timeout).and_then(|_| stream.try_clone()) {
+                    Ok(read_stream) => handle_request(read_stream, &options, solver),
+                    Err(err) => Response::error(500, &err.to_string()),
+                };
+            let _ = response.write_to(&mut stream);
+            linger(&stream, options.max_body);
+        });
+    }
+}
+
+// A client that stops sending or reading only holds its own thread until the
+// timeout, instead of forever.
+fn set_timeouts(stream: &TcpStream, timeout: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))
+}
+
+// Closing with unread request bytes resets the connection, which can drop the
+// response before the client reads it; read a bounded amount first.
+fn linger(stream: &TcpStream, max_body: usize) {
+    if stream.shutdown(Shutdown::Write).is_ok() {
+        let _ = io::copy(
+            &mut stream.take((MAX_HEAD + max_body) as u64),
+            &mut io::sink(),
+        );
+    }
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status: status,
+            body: json::object(&[("error", json::string(message))]),
+        }
+    }
+
+    fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            504 => "Gateway Timeout",
+            _ => "Internal Server Error",
+        };
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.body.len(),
+            self.body
+        )?;
+        out.flush()
+    }
+}
+
+// How a read timeout shows up, depending on the platform.
+fn is_timeout(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut
+}
+
+// One line of the request head, taken from what is left of `MAX_HEAD`.
+fn read_head_line<R: BufRead>(reader: &mut R, left: &mut usize) -> Result<String, Response> {
+    let mut line = String::new();
+    match reader.by_ref().take(*left as u64).read_line(&mut line) {
+        Ok(n) if n == *left && !line.ends_with('\n') => Err(Response::error(
+            431,
+            &format!(
+                "Request line and headers are larger than {} bytes",
+                MAX_HEAD
+            ),
+        )),
+        Ok(n) => {
+            *left -= n;
+            Ok(line)
+        }
+        Err(err) if is_timeout(&err) => Err(Response::error(408, "Timed out reading the request")),
+        Err(_) => Err(Response::error(400, "Invalid request head")),
+    }
+}
+
+fn handle_request<R: Read>(stream: R, options: &Options, solver: Solver) -> Response {
+    let mut reader = BufReader::new(stream);
+    let mut left = MAX_HEAD;
+    let request_line = match read_head_line(&mut reader, &mut left) {
+        Ok(line) => line,
+        Err(response) => return response,
+    };
+    let mut content_length = None;
+    loop {
+        let header = match read_head_line(&mut reader, &mut left) {
+            Ok(header) => header,
+            Err(response) => return response,
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        if let [name, value] = header.splitn(2, ':').collect::<Vec<&str>>()[..] {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse::<usize>() {
+                    Ok(length) => content_length = Some(length),
+                    Err(_) => return Response::error(400, "Invalid Content-Length"),
+                }
+            }
+        }
+    }
+
+    let (day, part) = match request_line.split_whitespace().collect::<Vec<&str>>()[..] {
+        [method, path, _] => match parse_path(path) {
+            Some(_) if method != "POST" => return Response::error(405, "Use POST"),
+            Some(Ok(day_and_part)) => day_and_part,
+            Some(Err(err)) => return Response::error(400, &err),
+            None => return Response::error(404, "Use POST /solve/{day}/{part}"),
+        },
+        _ => return Response::error(400, "Invalid request line"),
+    };
+    let content_length = match content_length {
+        Some(length) if length > options.max_body => {
+            return Response::error(
+                413,
+                &format!("Input is larger than {} bytes", options.max_body),
+            )
+        }
+        Some(length) => length,
+        None => return Response::error(411, "Content-Length is required"),
+    };
+    let mut body = vec![0; content_length];
+    match reader.read_exact(&mut body) {
+        Ok(()) => {}
+        Err(err) if is_timeout(&err) => {
+            return Response::error(408, "Timed out reading the request")
+        }
+        Err(_) => return Response::error(400, "Incomplete body"),
+    }
+    let input = match String::from_utf8(body) {
+        Ok(input) => input,
+        Err(_) => return Response::error(400, "Input is not UTF-8"),
+    };
+
+    let start = Instant::now();
+    match solver(day, part, input, options.timeout) {
+        Ok(solution) => Response {
+            status: 200,
+            body: json::object(&[
+                ("day", day.to_string()),
+                ("part", part.to_string()),
+                ("answer", json::string(&solution.answer)),
+                ("took", json::string(&solution.took)),
+                (
+                    "elapsed_ms",
+                    format!("{:.3}", start.elapsed().as_secs_f64() * 1000.0),
+                ),
+            ]),
+        },
+        Err(SolveError::Timeout) => Response::error(
+            504,
+            &format!("Timed out after {}s", options.timeout.as_secs_f64()),
+        ),
+        Err(SolveError::Failed(err)) => Response::error(500, &err),
+    }
+}
+
+fn parse_path(path: &str) -> Option<Result<(u32, u32), String>> {
+    match path.trim_matches('/').split('/').collect::<Vec<&str>>()[..] {
+        ["solve", day, part] => Some(match (day.parse(), part.parse()) {
+            (Ok(day), Ok(part)) if (1..=25).contains(&day) && (1..=2).contains(&part) => {
+                Ok((day, part))
+            }
+            _ => Err(format!("Unknown day {} or part {}", day, part)),
+        }),
+        _ => None,
+    }
+}
+
+#[allow(dead_code)]
+fn echo_solver(day: u32, part: u32, input: String, _: Duration) -> Result<Solution, SolveError> {
+    match input.as_str() {
+        "slow" => Err(SolveError::Timeout),
+        _ => Ok(Solution {
+            answer: format!("{}/{}: {}", day, part, input),
+            took: "1µs".to_string(),
+        }),
+    }
+}
+
+#[allow(dead_code)]
+fn request_over_loopback(request: &str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = Arc::new(Options {
+        port: addr.port(),
+        timeout: Duration::from_secs(1),
+        max_body: 16,
+        io_timeout: Duration::from_millis(200),
+    });
+    thread::spawn(move || serve(listener, options, echo_solver));
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_solve_over_loopback() {
+    let response =
+        request_over_loopback("POST /solve/1/2 HTTP/1.1\r\nContent-Length: 5\r\n\r\n1\n2\n3");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains(r#"{"day":1,"part":2,"answer":"1/2: 1\n2\n3","took":"1µs","#));
+}
+
+#[test]
+fn test_rejects_invalid_requests_over_loopback() {
+    let too_large = request_over_loopback(
+        "POST /solve/1/1 HTTP/1.1\r\nContent-Length: 17\r\n\r\n12345678901234567",
+    );
+    assert!(too_large.starts_with("HTTP/1.1 413 "));
+    let unknown_day =
+        request_over_loopback("POST /solve/26/1 HTTP/1.1\r\nContent-Length: 1\r\n\r\n1");
+    assert!(unknown_day.starts_with("HTTP/1.1 400 "));
+    let wrong_method = request_over_loopback("GET /solve/1/1 HTTP/1.1\r\n\r\n");
+    assert!(wrong_method.starts_with("HTTP/1.1 405 "));
+    let timeout =
+        request_over_loopback("POST /solve/1/1 HTTP/1.1\r\nContent-Length: 4\r\n\r\nslow");
+    assert!(timeout.starts_with("HTTP/1.1 504 "));
+}
+
+#[test]
+fn test_limits_request_head_over_loopback() {
+    let long_header = format!(
+        "POST /solve/1/1 HTTP/1.1\r\nX-Padding: {}\r\nContent-Length: 1\r\n\r\n1",
+        "a".repeat(MAX_HEAD)
+    );
+    assert!(request_over_loopback(&long_header).starts_with("HTTP/1.1 431 "));
+    let many_headers = format!(
+        "POST /solve/1/1 HTTP/1.1\r\n{}Content-Length: 1\r\n\r\n1",
+        "X-Padding: a\r\n".repeat(MAX_HEAD / 10)
+    );
+    assert!(request_over_loopback(&many_headers).starts_with("HTTP/1.1 431 "));
+    let silent = request_over_loopback("POST /solve/1/1 HTTP/1.1\r\n");
+    assert!(silent.starts_with("HTTP/1.1 408 "));
+}
+
+#[test]
+fn test_short_body_over_loopback() {
+    let short_body =
+        request_over_loopback("POST /solve/1/1 HTTP/1.1\r\nContent-Length: 10\r\n\r\n1\n2");
+    assert!(short_body.starts_with("HTTP/1.1 408 "));
+    assert!(short_body.contains("Timed out reading the request"));
+
+    // Without a socket the body just ends, which is not a timeout.
+    let early_eof = "POST /solve/1/1 HTTP/1.1\r\nContent-Length: 10\r\n\r\n1\n2".as_bytes();
+    let options = Options {
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_body: 16,
+        io_timeout: Duration::from_millis(200),
+    };
+    let response = handle_request(early_eof, &options, echo_solver);
+    assert_eq!(400, response.status);
+    assert!(response.body.contains("Incomplete body"));
+}
@@ -0,0 +1,398 @@
+use crate::json::Value;
+
+// The part of TOML that configuration files here need: `[dotted.table]`
+// headers, `key = value` pairs, strings, integers, floats, booleans, arrays
+// and inline tables. A table is defined once, by its header or as an inline
+// table, and integers must fit in an i64. Documents come back as the same
+// values `json::parse` returns, so either format can be read the same way.
+pub fn parse(s: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: s.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut root = Value::Object(vec![]);
+    let mut table: Vec<String> = vec![];
+    let mut defined: Vec<Vec<String>> = vec![];
+    let mut inline: Vec<Vec<String>> = vec![];
+    loop {
+        parser.skip_blank_lines();
+        match parser.peek() {
+            None => return Ok(root),
+            Some('[') => {
+                parser.pos += 1;
+                if parser.peek() == Some('[') {
+                    return Err(parser.error("arrays of tables are not supported"));
+                }
+                table = parser.dotted_key()?;
+                parser.expect(']')?;
+                if defined.contains(&table) {
+                    return Err(
+                        parser.error(&format!("table {} is defined twice", table.join(".")))
+                    );
+                }
+                if let Some(path) = inline.iter().find(|path| table.starts_with(path)) {
+                    return Err(parser.error(&format!(
+                        "{} is an inline table and cannot be extended",
+                        path.join(".")
+                    )));
+                }
+                defined.push(table.clone());
+                table_at(&mut root, &table).map_err(|err| parser.error(&err))?;
+            }
+            Some(_) => {
+                let key = parser.key()?;
+                parser.skip_spaces();
+                parser.expect('=')?;
+                let value = parser.value()?;
+                let fields = table_at(&mut root, &table).map_err(|err| parser.error(&err))?;
+                if fields.iter().any(|(name, _)| *name == key) {
+                    return Err(parser.error(&format!("duplicate key {}", key)));
+                }
+                if let Value::Object(_) = value {
+                    inline.push(table.iter().chain(Some(&key)).cloned().collect());
+                }
+                fields.push((key, value));
+            }
+        }
+        parser.end_of_line()?;
+    }
+}
+
+fn table_at<'a>(
+    root: &'a mut Value,
+    path: &[String],
+) -> Result<&'a mut Vec<(String, Value)>, String> {
+    let mut fields = match root {
+        Value::Object(fields) => fields,
+        _ => return Err("the document is not a table".to_string()),
+    };
+    for name in path {
+        let index = match fields.iter().position(|(key, _)| key == name) {
+            Some(index) => index,
+            None => {
+                fields.push((name.clone(), Value::Object(vec![])));
+                fields.len() - 1
+            }
+        };
+        fields = match &mut fields[index].1 {
+            Value::Object(fields) => fields,
+            _ => return Err(format!("{} is not a table", name)),
+        };
+    }
+    Ok(fields)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("Invalid TOML on line {}: {}", self.line, message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_spaces();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            c => Err(self.error(&format!("expected {:?}, found {:?}", expected, c))),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') => {
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') | Some('\r') => Ok(()),
+            Some(c) => Err(self.error(&format!("unexpected {:?} at the end of the line", c))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                match self.pos - start {
+                    0 => Err(self.error(&format!("expected a key, found {:?}", self.peek()))),
+                    _ => Ok(self.chars[start..self.pos].iter().collect()),
+                }
+            }
+        }
+    }
+
+    fn dotted_key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = vec![self.key()?];
+        self.skip_spaces();
+        while self.peek() == Some('.') {
+            self.pos += 1;
+            keys.push(self.key()?);
+            self.skip_spaces();
+        }
+        Ok(keys)
+    }
+
+    // Whitespace, newlines and comments inside arrays.
+    fn skip_array_space(&mut self) {
+        self.skip_blank_lines();
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t') | Some('f') => self.boolean(),
+            Some(c) if c == '-' || c == '+' || c.is_ascii_digit() => self.number(),
+            c => Err(self.error(&format!("expected a value, found {:?}", c))),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => {
+                        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+                        let c = u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                            .ok_or(self.error("invalid \\u escape"))?;
+                        s.push(c);
+                    }
+                    c => return Err(self.error(&format!("invalid escape {:?}", c))),
+                },
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(s),
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = vec![];
+        loop {
+            self.skip_array_space();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_array_space();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                c => return Err(self.error(&format!("expected ',' or ']', found {:?}", c))),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut fields: Vec<(String, Value)> = vec![];
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            let key = self.key()?;
+            self.expect('=')?;
+            let value = self.value()?;
+            if fields.iter().any(|(name, _)| *name == key) {
+                return Err(self.error(&format!("duplicate key {}", key)));
+            }
+            fields.push((key, value));
+            self.skip_spaces();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(fields)),
+                c => return Err(self.error(&format!("expected ',' or '}}', found {:?}", c))),
+            }
+        }
+    }
+
+    fn boolean(&mut self) -> Result<Value, String> {
+        for (word, value) in &[("true", true), ("false", false)] {
+            let end = self.pos + word.len();
+            if end <= self.chars.len()
+                && self.chars[self.pos..end].iter().collect::<String>() == *word
+            {
+                self.pos = end;
+                return Ok(Value::Bool(*value));
+            }
+        }
+        Err(self.error("expected true or false"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || "+-._eE".contains(c) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let number: String = self.chars[start..self.pos]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+        if !number.contains(|c| ".eE".contains(c)) {
+            return number
+                .parse()
+                .map(Value::Integer)
+                .map_err(|_| self.error(&format!("invalid integer {}", number)));
+        }
+        number
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| self.error(&format!("invalid number {}", number)))
+    }
+}
+
+#[test]
+fn test_parse() {
+    let document = r#"
+# A comment
+title = "passports" # and another
+count = 1_000
+
+[fields.hgt]
+type = 'measure'
+units = { cm = [150, 193], in = [59, 76] }
+
+[fields.ecl]
+values = [
+    "amb", "blu", # colours
+    "brn",
+]
+required = false
+"#;
+    let value = parse(document).unwrap();
+    assert_eq!(
+        Some("passports"),
+        value.get("title").and_then(|v| v.as_str())
+    );
+    assert_eq!(Some(1000), value.get("count").and_then(|v| v.as_i64()));
+    let hgt = value.get("fields").and_then(|f| f.get("hgt")).unwrap();
+    assert_eq!(Some("measure"), hgt.get("type").and_then(|v| v.as_str()));
+    assert_eq!(
+        Some(76),
+        hgt.get("units")
+            .and_then(|u| u.get("in"))
+            .and_then(|range| range.as_array())
+            .and_then(|range| range[1].as_i64())
+    );
+    let ecl = value.get("fields").and_then(|f| f.get("ecl")).unwrap();
+    assert_eq!(
+        3,
+        ecl.get("values").and_then(|v| v.as_array()).unwrap().len()
+    );
+    assert_eq!(Some(false), ecl.get("required").and_then(|v| v.as_bool()));
+
+    assert!(parse("a = 1\na = 2").is_err());
+    assert_eq!(
+        Err("Invalid TOML on line 4: table a is defined twice".to_string()),
+        parse("[a]\nx = 1\n[b]\n[a]\ny = 2")
+    );
+    assert!(parse("[a.b]\nx = 1\n[a]\ny = 2").is_ok());
+    assert!(parse("a = { x = 1 }\n[a]").is_err());
+    assert!(parse("a = { x = 1 }\n[a.b]").is_err());
+    assert!(parse("a = \"open").is_err());
+    assert!(parse("a = 1 2").is_err());
+    assert!(parse("[[tables]]").is_err());
+    assert_eq!(
+        Err("Invalid TOML on line 2: expected a value, found Some('?')".to_string()),
+        parse("a = 1\nb = ?")
+    );
+}
+
+#[test]
+fn test_parse_numbers() {
+    let value = parse("big = 9_007_199_254_740_993\nneg = -12\nf = 1.5\ne = 1e3").unwrap();
+    assert_eq!(Some(&Value::Integer(9007199254740993)), value.get("big"));
+    assert_eq!(Some(-12), value.get("neg").and_then(|v| v.as_i64()));
+    assert_eq!(Some(&Value::Number(1.5)), value.get("f"));
+    assert_eq!(None, value.get("e").and_then(|v| v.as_i64()));
+    assert_eq!(
+        Err("Invalid TOML on line 1: invalid integer 9223372036854775808".to_string()),
+        parse("a = 9223372036854775808")
+    );
+}
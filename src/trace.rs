@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+pub const DEBUG: u8 = 1;
+pub const TRACE: u8 = 2;
+
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_level(level: u8) {
+    LEVEL.store(level, Ordering::Relaxed);
+}
+
+// The macros check this before formatting anything, so disabled tracing is a
+// single relaxed load and a branch.
+#[inline(always)]
+pub fn enabled(level: u8) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level
+}
+
+pub fn source(module_path: &str) -> &str {
+    module_path.rsplit("::").next().unwrap_or(module_path)
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::DEBUG) {
+            eprintln!("[{}] {}", $crate::trace::source(module_path!()), format_args!($($arg)*));
+        }
+    };
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::TRACE) {
+            eprintln!("[{}] {}", $crate::trace::source(module_path!()), format_args!($($arg)*));
+        }
+    };
+}
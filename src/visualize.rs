@@ -0,0 +1,175 @@
+use crate::days;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+pub struct Options {
+    pub day_num: u32,
+    pub part: Option<u32>,
+    pub fps: f64,
+    pub step: bool,
+    pub plain: bool,
+    pub export: Option<PathBuf>,
+}
+
+pub fn parse_options(args: &[String]) -> Result<Options, String> {
+    let day = args.get(0).ok_or("You need to provide a day")?.trim();
+    let mut options = Options {
+        day_num: day
+            .parse()
+            .map_err(|_| format!("Invalid day number: {}", day))?,
+        part: None,
+        fps: 10.0,
+        step: false,
+        plain: !io::stdout().is_terminal(),
+        export: None,
+    };
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--part" => {
+                let part = rest.next().ok_or("--part needs 1 or 2")?;
+                options.part = Some(match part.as_str() {
+                    "1" => 1,
+                    "2" => 2,
+                    _ => return Err(format!("Invalid part: {}", part)),
+                });
+            }
+            "--fps" => {
+                let fps = rest.next().ok_or("--fps needs a frame rate")?;
+                options.fps = fps
+                    .parse()
+                    .ok()
+                    .filter(|fps: &f64| *fps > 0.0)
+                    .ok_or(format!("Invalid frame rate: {}", fps))?;
+            }
+            "--step" => options.step = true,
+            "--plain" => options.plain = true,
+            "--export" => {
+                let dir = rest.next().ok_or("--export needs a directory")?;
+                options.export = Some(PathBuf::from(dir));
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+pub fn run(options: Options, input: String) {
+    let visualize = match (options.part, days::get_visualizations(options.day_num)) {
+        (Some(1), (Some(visualize), _)) | (Some(2), (_, Some(visualize))) => visualize,
+        (None, (Some(visualize), _)) | (None, (None, Some(visualize))) => visualize,
+        _ => {
+            println!("Nothing to visualize for day {}", options.day_num);
+            return;
+        }
+    };
+    if let Some(dir) = &options.export {
+        fs::create_dir_all(dir).expect("Error while creating export directory");
+    }
+    let mut player = Player::new(options);
+    visualize(input, &mut |frame| player.show(frame));
+}
+
+struct Player {
+    options: Options,
+    frame_nr: usize,
+    paused: bool,
+    commands: Option<Receiver<String>>,
+}
+
+impl Player {
+    fn new(options: Options) -> Self {
+        let commands = if options.plain {
+            None
+        } else {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                for line in io::stdin().lock().lines() {
+                    if sender.send(line.unwrap_or_default()).is_err() {
+                        return;
+                    }
+                }
+            });
+            Some(receiver)
+        };
+        Self {
+            paused: options.step,
+            options: options,
+            frame_nr: 0,
+            commands: commands,
+        }
+    }
+
+    fn show(&mut self, frame: String) {
+        self.frame_nr += 1;
+        if let Some(dir) = &self.options.export {
+            let filename = dir.join(format!("frame_{:05}.txt", self.frame_nr));
+            fs::write(filename, &frame).expect("Error while exporting frame");
+        }
+        if self.options.plain {
+            write_frame(&mut io::stdout(), self.frame_nr, &frame).unwrap();
+            return;
+        }
+        print!("\x1b[2J\x1b[H");
+        write_frame(&mut io::stdout(), self.frame_nr, &frame).unwrap();
+        println!("[Enter] pause/resume  [n Enter] next frame  [q Enter] quit");
+        io::stdout().flush().unwrap();
+        self.wait();
+    }
+
+    fn wait(&mut self) {
+        let commands = self.commands.as_ref().unwrap();
+        if !self.paused {
+            thread::sleep(Duration::from_secs_f64(1.0 / self.options.fps));
+        }
+        loop {
+            let command = if self.paused {
+                commands.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                commands.try_recv()
+            };
+            match command.as_ref().map(|c| c.trim()) {
+                Ok("q") => std::process::exit(0),
+                Ok("n") => {
+                    self.paused = true;
+                    return;
+                }
+                Ok(_) => self.paused = !self.paused,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.paused = false;
+                    return;
+                }
+            }
+            if !self.paused {
+                return;
+            }
+        }
+    }
+}
+
+fn write_frame<W: Write>(out: &mut W, frame_nr: usize, frame: &str) -> io::Result<()> {
+    writeln!(out, "Frame {}", frame_nr)?;
+    writeln!(out, "{}", frame)?;
+    writeln!(out)
+}
+
+#[test]
+fn test_write_frames_of_seating_simulation() {
+    let seats = "L.L\nLLL\nL.L".to_string();
+    let (visualize, _) = days::get_visualizations(11);
+    let mut out = Vec::new();
+    let mut frame_nr = 0;
+    visualize.unwrap()(seats, &mut |frame| {
+        frame_nr += 1;
+        write_frame(&mut out, frame_nr, &frame).unwrap();
+    });
+    assert_eq!(
+        "Frame 1\nL.L\nLLL\nL.L\n\nFrame 2\n#.#\n###\n#.#\n\nFrame 3\n#.#\n#L#\n#.#\n\n",
+        String::from_utf8(out).unwrap()
+    );
+}